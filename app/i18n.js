@@ -23,7 +23,7 @@ window.APP_LANGUAGES = JSON.parse(__GMPUBLISHER_APP_LANGUAGES__);
 const RE_SPLIT_ERROR = /^(.*?)(?::([\s\S]*))?$/;
 export function translateError(error, data) {
 	if (data != null) {
-		return get(_)(error, { values: { data: data.toString() } });
+		return get(_)(error, { values: { data: Array.isArray(data) ? data.join('\n') : data.toString() } });
 	} else {
 		const match = error.match(RE_SPLIT_ERROR);
 		if (!match) {
@@ -36,6 +36,14 @@ export function translateError(error, data) {
 	}
 }
 
+export function translateEta(secs) {
+	if (secs >= 60) {
+		return get(_)('eta_minutes', { values: { mins: Math.floor(secs / 60), secs: secs % 60 } });
+	} else {
+		return get(_)('eta_seconds', { values: { secs } });
+	}
+}
+
 export function switchLanguage(switchLocale) {
 	const newLocale = switchLocale in window.APP_LANGUAGES ? switchLocale : (getLocaleFromNavigator() ?? 'en');
 	locale.set(newLocale);
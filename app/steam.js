@@ -223,6 +223,10 @@ listen('InstalledAddonsRefreshed', () => {
 	steam.InstalledAddons = [];
 });
 
+listen('InstalledAddonInvalidated', ({ payload: path }) => {
+	delete steam.Addons[path];
+});
+
 invoke('workshop_item_channel').then(transaction_id => {
 	const transaction = new Transaction(transaction_id);
 	transaction.listen(event => {
@@ -146,6 +146,20 @@ class Transaction {
 		return this;
 	}
 
+	setSummary(summary) {
+		this.summary = summary;
+		this.emit({ summary });
+
+		return this;
+	}
+
+	setEta(eta) {
+		this.eta = eta;
+		this.emit({ eta });
+
+		return this;
+	}
+
 	setProgress(progress) {
 		if (progress !== this.progress) {
 			this.progressInt = progress;
@@ -186,6 +200,14 @@ transactionEvent('Progress', ([ transaction, progress ]) => {
 	if (progress > (transaction.progressInt ?? 0)) transaction.setProgress(progress);
 });
 
+transactionEvent('Eta', ([ transaction, eta ]) => {
+	transaction.setEta(eta);
+});
+
+transactionEvent('Summary', ([ transaction, summary ]) => {
+	transaction.setSummary(summary);
+});
+
 transactionEvent('Cancelled', ([ transaction ]) => {
 	//console.log('transactionCancelled', transaction);
 	transaction.cancel(true);
@@ -286,6 +308,17 @@ invoke('websocket').then(port => {
 			case 6:
 			receiveTransactionEvent('ResetProgress', [id]);
 			break;
+
+			case 7:
+			receiveTransactionEvent('Eta', [id, view.getUint32(5)]);
+			break;
+
+			case 8:
+			{
+				const [summary, _] = read_json(5, view);
+				receiveTransactionEvent('Summary', [id, summary]);
+			}
+			break;
 		}
 	});
 });
@@ -89,11 +89,20 @@ pub struct Settings {
 
 	pub my_workshop_local_paths: HashMap<PublishedFileId, PathBuf>,
 	pub upscale_addon_icon: bool,
+	/// Animated GIF icons are flattened to their first frame unless this is set
+	pub reject_animated_icons: bool,
+	/// Prefilled when publishing a new addon
+	pub default_addon_type: Option<String>,
+	/// Prefilled when publishing a new addon
+	pub default_tags: Vec<String>,
 
 	pub language: Option<String>,
 
 	pub extract_overwrite_mode: ExtractionOverwriteMode,
 
+	/// Buffer size in bytes for reading GMAs and writing GMAs and extracted files. Defaults to 8 KiB
+	pub io_buffer_size: Option<usize>,
+
 	pub color_neutral: u32,
 	pub color_error: u32,
 	pub color_success: u32,
@@ -118,11 +127,16 @@ impl Default for Settings {
 			ignore_globs: Vec::new(),
 			my_workshop_local_paths: HashMap::new(),
 			upscale_addon_icon: true,
+			reject_animated_icons: false,
+			default_addon_type: None,
+			default_tags: Vec::new(),
 
 			language: None,
 
 			extract_overwrite_mode: ExtractionOverwriteMode::default(),
 
+			io_buffer_size: None,
+
 			color_neutral: 28103,
 			color_error: 11010048,
 			color_success: 3188321,
@@ -132,10 +146,12 @@ impl Default for Settings {
 impl Settings {
 	pub fn init() -> Settings {
 		println!("Initializing Settings...");
-		match Settings::load(false) {
+		let settings = match Settings::load(false) {
 			Ok(settings) => settings,
 			Err(_) => Settings::default(),
-		}
+		};
+		crate::gma::set_io_buffer_size(settings.io_buffer_size);
+		settings
 	}
 
 	fn load(sanitize: bool) -> Result<Settings, anyhow::Error> {
@@ -166,6 +182,11 @@ impl Settings {
 					self.extract_destination = ExtractDestination::Directory(path.to_owned());
 				}
 			}
+			ExtractDestination::MountReady(path) => {
+				if !path.is_dir() {
+					self.extract_destination = ExtractDestination::default();
+				}
+			}
 			ExtractDestination::Downloads => {
 				if app_data!().downloads_dir().is_none() {
 					self.extract_destination = ExtractDestination::default();
@@ -262,6 +283,38 @@ impl AppData {
 		}
 	}
 
+	pub fn addons_dir(&self) -> Option<PathBuf> {
+		self.gmod_dir().map(|mut path| {
+			path.push("GarrysMod");
+			path.push("addons");
+			path
+		})
+	}
+
+	/// Every addon folder and loose .gma in the addons directory, sorted by path
+	pub fn list_installed_addons(&self) -> Vec<PathBuf> {
+		let read_dir = match self.addons_dir().and_then(|addons_dir| addons_dir.read_dir().ok()) {
+			Some(read_dir) => read_dir,
+			None => return Vec::new(),
+		};
+
+		let mut addons: Vec<PathBuf> = read_dir
+			.filter_map(|entry| {
+				let entry = entry.ok()?;
+				let file_type = entry.file_type().ok()?;
+				let path = entry.path();
+				if file_type.is_dir() || (file_type.is_file() && path.extension().map_or(false, |extension| extension.eq_ignore_ascii_case("gma"))) {
+					Some(path)
+				} else {
+					None
+				}
+			})
+			.collect();
+
+		addons.sort_unstable();
+		addons
+	}
+
 	pub fn temp_dir(&self) -> RwLockCow<'_, PathBuf> {
 		let lock = self.settings.read();
 		if let Some(ref temp) = lock.temp {
@@ -350,6 +403,8 @@ pub fn update_settings(mut settings: Settings) -> bool {
 
 	let rediscover_addons = app_data!().settings.read().gmod != settings.gmod;
 
+	crate::gma::set_io_buffer_size(settings.io_buffer_size);
+
 	*app_data!().settings.write() = settings;
 
 	if rediscover_addons {
@@ -362,6 +417,11 @@ pub fn update_settings(mut settings: Settings) -> bool {
 	true
 }
 
+#[tauri::command]
+pub fn list_installed_addons() -> Vec<PathBuf> {
+	app_data!().list_installed_addons()
+}
+
 #[tauri::command]
 pub fn validate_gmod(mut path: PathBuf) -> bool {
 	path.push("GarrysMod");
@@ -1,10 +1,16 @@
 use std::path::PathBuf;
 
+use steamworks::PublishedFileId;
+
 use crate::{
-	gma::{ExtractDestination, ExtractGMAMut},
+	gma::{ExtractDestination, ExtractGMAMut, ExtractOptions, GMAMetadata},
+	steam::publishing::{self, PublishError, WorkshopIcon},
 	GMAFile,
 };
 
+/// How long the CLI waits for Steam to start up before giving up
+const STEAM_READY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 lazy_static! {
 	pub static ref CLI_MODE: bool = std::env::args_os().len() > 1;
 }
@@ -22,63 +28,74 @@ pub(super) fn stdin() -> bool {
 	let command = Command::new("gmpublisher");
 
 	let matches = command
-	.version(env!("CARGO_PKG_VERSION"))
-	.author("William Venner <william@venner.io>")
-	.about("Publish, extract and work with GMA files")
-	.args(&[
-		Arg::new("extract")
-		.short('e')
-		.long("extract")
-		.value_name("FILE")
-		.help("Extracts a .GMA file"),
-		//.conflicts_with_all(&["update", "in", "changes", "icon"]),
-
-		Arg::new("out")
-		.short('o')
-		.long("out")
-		.value_name("PATH")
-		.help("Sets the output path for extracting GMAs. Defaults to the temp directory.")
-		.requires("extract")
-		//.conflicts_with_all(&["update", "in", "changes", "icon"])
-	])
-	/*.args(&[
-		Arg::with_name("update")
-		.short('u')
-		.long("update")
-		.value_name("PublishedFileId")
-		.takes_value(true)
-		.help("Publishes an update.")
-		.requires("in")
-		.conflicts_with_all(&["out", "extract"]),
-
-		Arg::with_name("in")
-		.long("in")
-		.value_name("PATH")
-		.takes_value(true)
-		.help("Sets the directory the GMA for updating will be built from.")
-		.requires("update")
-		.conflicts_with_all(&["out", "extract"]),
-
-		Arg::with_name("changes")
-		.long("changes")
-		.value_name("CHANGES")
-		.takes_value(true)
-		.help("Sets the changelog for an update.")
-		.requires("update")
-		.conflicts_with_all(&["out", "extract"]),
-
-		Arg::with_name("icon")
-		.long("icon")
-		.value_name("PATH")
-		.takes_value(true)
-		.help("Path to a (max 1 MB) JPG/PNG/GIF file for Workshop preview image updating.")
-		.requires("update")
-		.conflicts_with_all(&["out", "extract"])
-	])*/
-	.get_matches();
+		.version(env!("CARGO_PKG_VERSION"))
+		.author("William Venner <william@venner.io>")
+		.about("Publish, extract and work with GMA files")
+		.args(&[
+			Arg::new("extract")
+				.short('e')
+				.long("extract")
+				.value_name("FILE")
+				.help("Extracts a .GMA file")
+				.conflicts_with_all(["update", "in", "changes", "icon"]),
+			Arg::new("out")
+				.short('o')
+				.long("out")
+				.value_name("PATH")
+				.help("Sets the output path for extracting GMAs. Defaults to the temp directory.")
+				.requires("extract")
+				.conflicts_with_all(["update", "in", "changes", "icon"]),
+			Arg::new("tree")
+				.long("tree")
+				.value_name("FILE")
+				.help("Prints the contents of a .GMA file as a JSON directory tree.")
+				.conflicts_with_all(["extract", "update", "in", "changes", "icon"]),
+			Arg::new("report")
+				.long("report")
+				.action(clap::ArgAction::SetTrue)
+				.help("Prints a JSON summary of the extraction instead of opening the extracted folder.")
+				.requires("extract"),
+			Arg::new("only-changed")
+				.long("only-changed")
+				.action(clap::ArgAction::SetTrue)
+				.help("Extracts into the existing output folder, only writing files that differ from what's already there.")
+				.requires("out"),
+		])
+		.args(&[
+			Arg::new("update")
+				.short('u')
+				.long("update")
+				.value_name("PublishedFileId")
+				.value_parser(clap::value_parser!(u64))
+				.help("Publishes an update.")
+				.requires("in"),
+			Arg::new("in")
+				.long("in")
+				.value_name("PATH")
+				.help("Sets the directory the GMA for updating will be built from.")
+				.requires("update"),
+			Arg::new("changes")
+				.long("changes")
+				.value_name("CHANGES")
+				.help("Sets the changelog for an update.")
+				.requires("update"),
+			Arg::new("icon")
+				.long("icon")
+				.value_name("PATH")
+				.help("Path to a (max 1 MB) JPG/PNG/GIF file for Workshop preview image updating.")
+				.requires("update"),
+		])
+		.get_matches();
 
 	dprintln!("{:#?}", matches);
 
+	if let Some(tree_path) = matches.get_one::<String>("tree") {
+		match GMAFile::open(tree_path).and_then(|mut gma| gma.entry_tree()) {
+			Ok(tree) => std::println!("{}", serde_json::to_string_pretty(&tree).unwrap()),
+			Err(err) => std::eprintln!("Error: {:#?}", err),
+		}
+	}
+
 	if let Some(extract_path) = matches.get_one::<String>("extract") {
 		let extract_path = PathBuf::from(extract_path);
 
@@ -93,11 +110,86 @@ pub(super) fn stdin() -> bool {
 				None => ExtractDestination::Temp,
 			};
 
-			if let Err(err) = gma.extract(dest, &transaction!(), true, true) {
+			if matches.get_flag("report") {
+				let options = ExtractOptions {
+					only_changed: matches.get_flag("only-changed"),
+					..Default::default()
+				};
+				match gma.extract_report(dest, options, &transaction!(), false, true) {
+					Ok(report) => std::println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+					Err(err) => std::eprintln!("Error: {:#?}", err),
+				}
+			} else if matches.get_flag("only-changed") {
+				let options = ExtractOptions {
+					only_changed: true,
+					..Default::default()
+				};
+				match gma.extract_report(dest, options, &transaction!(), false, true) {
+					Ok(report) => std::println!("Extracted {} changed, skipped {} unchanged", report.files_written, report.files_unchanged),
+					Err(err) => std::eprintln!("Error: {:#?}", err),
+				}
+			} else if let Err(err) = gma.extract(dest, &transaction!(), true, true) {
 				std::eprintln!("Error: {:#?}", err);
 			}
 		}
 	}
 
+	if let Some(update_id) = matches.get_one::<u64>("update") {
+		let content_path = PathBuf::from(matches.get_one::<String>("in").unwrap());
+
+		let metadata = match std::fs::read_to_string(content_path.join("addon.json"))
+			.ok()
+			.and_then(|json| crate::from_str_lenient::<GMAMetadata>(&json).ok())
+			.map(|(metadata, lenient)| {
+				if lenient {
+					std::eprintln!("Warning: addon.json has comments or trailing commas, which aren't valid JSON. Please fix it.");
+				}
+				metadata
+			}) {
+			Some(GMAMetadata::Standard { title, addon_type, tags, .. }) => (title, addon_type, tags),
+			_ => {
+				std::eprintln!("Couldn't read addon.json in the provided directory.");
+				return true;
+			}
+		};
+
+		let preview = match matches.get_one::<String>("icon") {
+			Some(icon) => match WorkshopIcon::new(icon, false) {
+				Ok(icon) => Some(icon),
+				Err(err) => {
+					std::eprintln!("Error: {}", err);
+					return true;
+				}
+			},
+			None => None,
+		};
+
+		if let Err(err) = steam!().wait_ready(STEAM_READY_TIMEOUT) {
+			std::eprintln!("Error: {}", err);
+			return true;
+		}
+
+		let (title, addon_type, tags) = metadata;
+		match publishing::publish_blocking(
+			&content_path,
+			preview,
+			title,
+			tags,
+			addon_type,
+			Some(PublishedFileId(*update_id)),
+			matches.get_one::<String>("changes").cloned(),
+			&transaction!(),
+		) {
+			Ok(id) => {
+				std::println!("Published update to https://steamcommunity.com/sharedfiles/filedetails/?id={}", id.0);
+			}
+			Err(PublishError::LegalAgreementRequired(id)) => {
+				std::println!("Published update to https://steamcommunity.com/sharedfiles/filedetails/?id={}", id.0);
+				std::println!("You need to accept the Steam Workshop legal agreement before this update is visible.");
+			}
+			Err(err) => std::eprintln!("Error: {}", err),
+		}
+	}
+
 	true
 }
@@ -16,9 +16,13 @@ pub fn invoke_handler() -> impl Fn(tauri::Invoke<tauri::Wry>) + Send + Sync + 's
 		crate::transactions::cancel_transaction,
 		crate::appdata::update_settings,
 		crate::appdata::validate_gmod,
+		crate::appdata::list_installed_addons,
 		crate::appdata::window_resized,
 		crate::game_addons::browse_installed_addons,
+		crate::game_addons::summarize_installed_addons,
+		crate::game_addons::installed_addons_newer_than,
 		crate::game_addons::get_installed_addon,
+		crate::game_addons::invalidate_installed_addon,
 		crate::game_addons::downloader_extract_gmas,
 		crate::steam::is_steam_connected,
 		crate::steam::get_current_user,
@@ -28,18 +32,35 @@ pub fn invoke_handler() -> impl Fn(tauri::Invoke<tauri::Wry>) + Send + Sync + 's
 		crate::steam::workshop::browse_my_workshop,
 		crate::steam::workshop::workshop_item_channel,
 		crate::steam::downloads::workshop_download,
+		crate::steam::downloads::extract_workshop_item,
 		crate::steam::publishing::verify_whitelist,
 		crate::steam::publishing::publish,
 		crate::steam::publishing::verify_icon,
 		crate::steam::publishing::publish_icon,
+		crate::steam::publishing::create_collection,
+		crate::steam::publishing::sync_workshop_metadata,
 		crate::steam::subscriptions::browse_subscribed_addons,
 		crate::addon_size_analyzer::addon_size_analyzer,
 		crate::content_generator::get_content_generator_manifests,
 		crate::content_generator::update_content_generator_manifest,
 		crate::gma::preview::preview_gma,
+		crate::gma::preview::preview_entry_tree,
+		crate::gma::preview::generate_gma_preview,
+		crate::gma::preview::preview_compression_ratio,
+		crate::gma::preview::preview_addon_json,
+		crate::gma::preview::preview_suggested_tags,
+		crate::gma::preview::preview_extract_destination,
 		crate::gma::preview::extract_preview_entry,
 		crate::gma::preview::extract_preview_gma,
 		crate::gma::extract::extract_gma,
+		crate::gma::extract::open_gma_entry,
+		crate::gma::extract::extract_destinations,
+		crate::gma::extract::extract_space_check,
+		crate::gma::verify::verify_gma,
+		crate::gma::write::merge_gmas,
+		crate::gma::write::estimate_compressed_size,
+		crate::gma::whitelist::whitelist_check,
+		crate::gma::whitelist::whitelist_is_ignored,
 		crate::search::search,
 		crate::search::search_channel,
 		crate::search::full_search,
@@ -66,6 +87,7 @@ pub fn invoke_handler() -> impl Fn(tauri::Invoke<tauri::Wry>) + Send + Sync + 's
 pub fn free_caches() {
 	crate::game_addons::free_caches();
 	crate::steam::workshop::free_caches();
+	crate::gma::extract::free_caches();
 	search!().clear();
 }
 
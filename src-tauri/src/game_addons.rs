@@ -6,7 +6,7 @@ use std::{
 		atomic::{AtomicU8, Ordering},
 		mpsc, Arc,
 	},
-	time::SystemTime,
+	time::{Duration, SystemTime},
 };
 
 use lazy_static::lazy_static;
@@ -45,7 +45,8 @@ pub struct GameAddons {
 	discovered: AtomicU8,
 	paths: RwLock<HashMap<PathBuf, Arc<Addon>>>,
 	pages: RwLock<Vec<Arc<Addon>>>,
-	external: RwLock<HashMap<PathBuf, Option<Arc<Addon>>>>,
+	/// GMAs opened from outside the addons folders, keyed by canonical path, along with their modification time when they were read
+	external: RwLock<HashMap<PathBuf, (Option<SystemTime>, Option<Arc<Addon>>)>>,
 }
 
 impl GameAddons {
@@ -118,8 +119,8 @@ impl GameAddons {
 	pub fn refresh(&self) {
 		self.discovered.store(Discovered::Discovering.into(), Ordering::Release);
 
-		let mut gmod = if let Some(gmod) = app_data!().gmod_dir() {
-			gmod
+		let (mut gmod, addons_dir) = if let Some(dirs) = app_data!().gmod_dir().zip(app_data!().addons_dir()) {
+			dirs
 		} else {
 			*self.paths.write() = HashMap::new();
 			*self.pages.write() = Vec::new();
@@ -130,8 +131,6 @@ impl GameAddons {
 
 		let workshop_content_dir = GameAddons::get_workshop_content_dir(&gmod);
 
-		let addons_dir = gmod.join("GarrysMod/addons");
-
 		gmod.push("GarrysMod/cache/workshop");
 		let cache_dir = gmod;
 
@@ -334,12 +333,62 @@ pub fn browse_installed_addons(page: u32) -> InstalledAddonsPage {
 	)
 }
 
+/// How many installed addons there are of each addon type
+#[tauri::command]
+pub fn summarize_installed_addons() -> HashMap<String, usize> {
+	crate::gma::summarize_library(game_addons!().get_addons().iter().map(|addon| addon.installed()))
+}
+
+/// Installed addons packed or updated after `since` (seconds since the Unix epoch), newest first
+#[tauri::command]
+pub fn installed_addons_newer_than(since: u64) -> Vec<Arc<Addon>> {
+	let since = SystemTime::UNIX_EPOCH + Duration::from_secs(since);
+	game_addons!()
+		.get_addons()
+		.iter()
+		.filter(|addon| addon.installed().is_newer_than(since))
+		.cloned()
+		.collect()
+}
+
+fn modified<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
+	path.as_ref().metadata().and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Forgets everything cached about the GMA at `path`, e.g. after it was edited outside of gmpublisher, and tells the frontend
+pub fn invalidate<P: AsRef<Path>>(path: P) {
+	let original = path.as_ref();
+	let path = crate::path::canonicalize(original.to_path_buf());
+
+	let invalidated = game_addons!().external.write().remove(&path).is_some();
+	// Decompressed GMAs are cached by whatever path they were opened with
+	let invalidated = crate::gma::extract::forget_decompressed(original) | crate::gma::extract::forget_decompressed(&path) | invalidated;
+
+	if invalidated {
+		webview_emit!("InstalledAddonInvalidated", path);
+	}
+}
+
+#[tauri::command]
+pub fn invalidate_installed_addon(path: PathBuf) {
+	invalidate(path);
+}
+
 #[tauri::command]
 pub fn get_installed_addon(path: PathBuf) -> Option<Arc<Addon>> {
 	game_addons!().discover_addons();
 
-	if let Some(cached) = game_addons!().external.read().get(&path) {
-		return cached.clone();
+	let path = crate::path::canonicalize(path);
+
+	let stale = match game_addons!().external.read().get(&path) {
+		Some((cached_modified, cached)) if *cached_modified == modified(&path) => return cached.clone(),
+		Some(_) => true,
+		None => false,
+	};
+
+	if stale {
+		dprintln!("{} changed on disk, reading it again", path.display());
+		invalidate(&path);
 	}
 
 	if path.is_absolute() && path.is_file() && crate::path::has_extension(&path, "gma") {
@@ -352,11 +401,11 @@ pub fn get_installed_addon(path: PathBuf) -> Option<Arc<Addon>> {
 				ignore! { gma.metadata() };
 
 				let gma = Arc::new(Addon::Installed(gma));
-				game_addons!().external.write().insert(path, Some(gma.clone()));
+				game_addons!().external.write().insert(path.clone(), (modified(&path), Some(gma.clone())));
 				Some(gma)
 			}
 			Err(_) => {
-				game_addons!().external.write().insert(path, None);
+				game_addons!().external.write().insert(path.clone(), (modified(&path), None));
 				None
 			}
 		}
@@ -375,6 +424,8 @@ pub fn downloader_extract_gmas(paths: Vec<PathBuf>) {
 				None => false,
 			} {
 			if let Ok(mut gma) = GMAFile::open(&path) {
+				ignore! { gma.enrich_from_workshop_sidecar() };
+
 				let transaction = transaction!();
 				webview_emit!(
 					"ExtractionStarted",
@@ -0,0 +1,218 @@
+use std::{collections::HashSet, fs::File, path::Path};
+
+use path_slash::PathExt;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use super::{EntryHash, GMAEntry, GMAError, GMAFile};
+
+/// Files that differ between a GMA and a folder it was extracted to. Paths are relative and sorted
+#[derive(Debug, Clone, Serialize, Default, PartialEq, Eq)]
+pub struct FolderDiff {
+	/// In the folder, but not the GMA
+	pub added: Vec<String>,
+	/// In the GMA, but not the folder
+	pub removed: Vec<String>,
+	/// In both, but with a different size or CRC
+	pub modified: Vec<String>,
+}
+impl FolderDiff {
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+	}
+}
+
+/// Whether two GMAs contain the same paths, e.g. a rebuilt GMA and the one it was extracted from. Paths are sorted
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ManifestComparison {
+	/// Same number of entries and the same paths, regardless of order
+	pub matches: bool,
+	pub entry_count: usize,
+	pub other_entry_count: usize,
+	/// In this GMA, but not the other
+	pub missing: Vec<String>,
+	/// In the other GMA, but not this one
+	pub extra: Vec<String>,
+}
+
+fn file_modified(path: &Path, entry: &GMAEntry, entry_hash: EntryHash) -> Result<bool, std::io::Error> {
+	// Compressed entries store their compressed size, so only the CRC can tell
+	if !entry.compressed && path.metadata()?.len() != entry.size {
+		return Ok(true);
+	}
+
+	// Some tools don't compute CRCs, in which case the size is all we have to go on
+	if entry.crc == 0 {
+		return Ok(false);
+	}
+
+	let mut hasher = entry_hash.hasher();
+	std::io::copy(&mut File::open(path)?, &mut hasher)?;
+	Ok(hasher.finalize() != entry.crc)
+}
+
+impl GMAFile {
+	/// Compares the entries with the files currently in `folder`, e.g. to check for changes made after extracting.
+	/// The `addon.json` written on extraction isn't counted as added
+	pub fn diff_against_folder(&mut self, folder: &Path) -> Result<FolderDiff, GMAError> {
+		main_thread_forbidden!();
+
+		self.entries()?;
+		let entries = self.entries.as_ref().unwrap();
+		let entry_hash = self.entry_hash();
+
+		let mut diff = FolderDiff::default();
+		let mut seen = HashSet::with_capacity(entries.len());
+
+		for entry in WalkDir::new(folder).min_depth(1).into_iter().filter_map(|entry| entry.ok()) {
+			if !entry.file_type().is_file() {
+				continue;
+			}
+
+			let relative_path = match entry.path().strip_prefix(folder) {
+				Ok(relative_path) => relative_path.to_slash_lossy().to_lowercase(),
+				Err(_) => continue,
+			};
+
+			match entries.get(&relative_path) {
+				Some(gma_entry) => {
+					seen.insert(gma_entry.path.as_str());
+					if file_modified(entry.path(), gma_entry, entry_hash).unwrap_or(true) {
+						diff.modified.push(relative_path);
+					}
+				}
+				None if relative_path == "addon.json" => {}
+				None => diff.added.push(relative_path),
+			}
+		}
+
+		diff.removed = entries.keys().filter(|path| !seen.contains(path.as_str())).cloned().collect();
+
+		diff.added.sort_unstable();
+		diff.removed.sort_unstable();
+		diff.modified.sort_unstable();
+
+		Ok(diff)
+	}
+
+	/// Compares which paths this GMA and `other` contain, without looking at their contents
+	pub fn compare_manifest(&mut self, other: &mut GMAFile) -> Result<ManifestComparison, GMAError> {
+		self.entries()?;
+		other.entries()?;
+
+		let entries = self.entries.as_ref().unwrap();
+		let other_entries = other.entries.as_ref().unwrap();
+
+		let mut missing: Vec<String> = entries.keys().filter(|path| !other_entries.contains_key(*path)).cloned().collect();
+		let mut extra: Vec<String> = other_entries.keys().filter(|path| !entries.contains_key(*path)).cloned().collect();
+
+		missing.sort_unstable();
+		extra.sort_unstable();
+
+		Ok(ManifestComparison {
+			matches: missing.is_empty() && extra.is_empty() && entries.len() == other_entries.len(),
+			entry_count: entries.len(),
+			other_entry_count: other_entries.len(),
+			missing,
+			extra,
+		})
+	}
+}
+
+#[test]
+fn test_diff_against_folder() {
+	let path = std::env::temp_dir().join("gmpublisher_test_diff_against_folder.gma");
+	std::fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[
+				("lua/autorun/a.lua", b"print('a')"),
+				("lua/autorun/b.lua", b"print('b')"),
+				("lua/autorun/c.lua", b"print('c')"),
+			],
+		),
+	)
+	.unwrap();
+
+	let folder = std::env::temp_dir().join("gmpublisher_test_diff_against_folder");
+	std::fs::remove_dir_all(&folder).ok();
+	std::fs::create_dir_all(folder.join("lua/autorun")).unwrap();
+	std::fs::write(folder.join("addon.json"), b"{}").unwrap();
+	std::fs::write(folder.join("lua/autorun/a.lua"), b"print('a')").unwrap();
+	std::fs::write(folder.join("lua/autorun/b.lua"), b"print('B')").unwrap();
+	std::fs::write(folder.join("lua/autorun/d.lua"), b"print('d')").unwrap();
+
+	let diff = GMAFile::open(&path).unwrap().diff_against_folder(&folder).unwrap();
+	assert_eq!(
+		diff,
+		FolderDiff {
+			added: vec!["lua/autorun/d.lua".to_string()],
+			removed: vec!["lua/autorun/c.lua".to_string()],
+			modified: vec!["lua/autorun/b.lua".to_string()],
+		}
+	);
+
+	std::fs::remove_dir_all(folder).ok();
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_compare_manifest() {
+	let dir = std::env::temp_dir();
+
+	let original = dir.join("gmpublisher_test_compare_manifest.gma");
+	std::fs::write(
+		&original,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("lua/autorun/b.lua", b"print('b')")],
+		),
+	)
+	.unwrap();
+
+	let reordered = dir.join("gmpublisher_test_compare_manifest_reordered.gma");
+	std::fs::write(
+		&reordered,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/b.lua", b"print('B')"), ("lua/autorun/a.lua", b"print('a')")],
+		),
+	)
+	.unwrap();
+
+	let rebuilt = dir.join("gmpublisher_test_compare_manifest_rebuilt.gma");
+	std::fs::write(
+		&rebuilt,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("lua/autorun/c.lua", b"print('c')")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&original).unwrap();
+
+	let comparison = gma.compare_manifest(&mut GMAFile::open(&reordered).unwrap()).unwrap();
+	assert!(comparison.matches);
+	assert!(comparison.missing.is_empty() && comparison.extra.is_empty());
+
+	assert_eq!(
+		gma.compare_manifest(&mut GMAFile::open(&rebuilt).unwrap()).unwrap(),
+		ManifestComparison {
+			matches: false,
+			entry_count: 2,
+			other_entry_count: 2,
+			missing: vec!["lua/autorun/b.lua".to_string()],
+			extra: vec!["lua/autorun/c.lua".to_string()],
+		}
+	);
+
+	for path in [original, reordered, rebuilt] {
+		std::fs::remove_file(path).ok();
+	}
+}
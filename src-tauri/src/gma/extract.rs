@@ -1,26 +1,117 @@
 use std::{
+	collections::VecDeque,
 	fs::{self, File},
 	io::{BufWriter, Cursor, Read, SeekFrom},
-	path::{Path, PathBuf},
+	path::{Component, Path, PathBuf},
 	sync::{
-		atomic::{AtomicBool, AtomicUsize, Ordering},
+		atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
 		Arc,
 	},
+	time::{Duration, Instant, SystemTime},
 };
 
-use crate::{app_data, transactions::Transaction};
+use crate::{app_data, transactions::Transaction, ArcBytes};
 
-use super::{whitelist, GMAEntry, GMAError, GMAFile, GMAMetadata, GMAReader};
+use super::{io_buffer_size, vpk::VpkWriter, whitelist, GMAEntry, GMAError, GMAFile, GMAMetadata, GMAReader};
 
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use path_slash::PathExt;
 use rayon::{
-	iter::{IntoParallelRefIterator, ParallelIterator},
+	iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
 	ThreadPool,
 };
 use serde::{Deserialize, Serialize};
+use steamworks::PublishedFileId;
+use walkdir::WalkDir;
 
 lazy_static! {
 	pub static ref THREAD_POOL: ThreadPool = thread_pool!();
+
+	/// Decompressed GMAs keyed by their source path, along with the source's modification time when they were decompressed
+	static ref DECOMPRESSED: Mutex<IndexMap<PathBuf, (SystemTime, ArcBytes)>> = Mutex::new(IndexMap::new());
+}
+
+/// How many decompressed GMAs to keep in memory. The oldest is evicted first
+const DECOMPRESSED_CACHE_SIZE: usize = 4;
+
+pub fn free_caches() {
+	DECOMPRESSED.lock().clear();
+}
+
+/// Drops the decompressed copy of the GMA at `path`, if there is one
+pub(crate) fn forget_decompressed(path: &Path) -> bool {
+	DECOMPRESSED.lock().shift_remove(path).is_some()
+}
+
+/// Buffer size used when reading a whole GMA front to back during extraction (4 MiB)
+pub(super) const SEQUENTIAL_READ_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Default cap on the memory the LZMA decoder may use for its dictionary (512 MiB)
+pub const DEFAULT_LZMA_MEMLIMIT: u64 = 512 * 1024 * 1024;
+
+/// How far back the extraction ETA looks when measuring throughput
+const ETA_WINDOW: Duration = Duration::from_secs(5);
+
+/// Estimates how long an extraction has left from its throughput over the last `ETA_WINDOW`
+struct EtaEstimator {
+	bytes_total: u64,
+	bytes_done: u64,
+	samples: VecDeque<(Instant, u64)>,
+	last_eta: Option<u32>,
+}
+impl EtaEstimator {
+	fn new(bytes_total: u64) -> Self {
+		Self::new_at(Instant::now(), bytes_total)
+	}
+
+	fn new_at(now: Instant, bytes_total: u64) -> Self {
+		let mut samples = VecDeque::new();
+		samples.push_back((now, 0));
+		Self {
+			bytes_total,
+			bytes_done: 0,
+			samples,
+			last_eta: None,
+		}
+	}
+
+	/// Records `bytes` more having been extracted, returning the ETA in seconds if it changed
+	fn advance(&mut self, bytes: u64) -> Option<u32> {
+		self.advance_at(Instant::now(), bytes)
+	}
+
+	fn advance_at(&mut self, now: Instant, bytes: u64) -> Option<u32> {
+		self.bytes_done += bytes;
+		self.samples.push_back((now, self.bytes_done));
+
+		// Keep the newest sample from outside the window so the window is always fully covered
+		while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= ETA_WINDOW {
+			self.samples.pop_front();
+		}
+
+		let eta = self.eta(now)?;
+		if self.last_eta == Some(eta) {
+			None
+		} else {
+			self.last_eta = Some(eta);
+			Some(eta)
+		}
+	}
+
+	fn eta(&self, now: Instant) -> Option<u32> {
+		let (since, bytes_since) = *self.samples.front()?;
+
+		let elapsed = now.duration_since(since).as_secs_f64();
+		let bytes = self.bytes_done - bytes_since;
+		if elapsed <= 0. || bytes == 0 {
+			return None;
+		}
+
+		let remaining = self.bytes_total.saturating_sub(self.bytes_done) as f64;
+		Some(f64::min((remaining / (bytes as f64 / elapsed)).ceil(), u32::MAX as f64) as u32)
+	}
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -41,35 +132,165 @@ pub enum ExtractDestination {
 	Directory(PathBuf),
 	/// path/to/addon/addon_name_123456790/*
 	NamedDirectory(PathBuf),
+	/// A folder Garry's Mod can mount as-is when `path` is `GarrysMod/garrysmod/addons`:
+	///
+	/// ```text
+	/// path/
+	///   addon_name_123456790/   lowercase ASCII letters, digits and underscores only
+	///     addon.json            always written, even for legacy GMAs
+	///     lua/ materials/ ...   the GMA's entries, at the same paths they have in the GMA
+	/// ```
+	MountReady(PathBuf),
+	/// path/to/addon.vpk, a single-file VPK holding the addon.json and the entries instead of loose files. Overwritten if it exists
+	Vpk(PathBuf),
+}
+/// How the extracted addon.json is formatted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum JsonStyle {
+	/// Indented with two spaces
+	#[default]
+	Pretty,
+	Minified,
+	/// Indented with tabs
+	Tabs,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub struct ExtractOptions {
+	/// Extract into the existing folder instead of recycling it, overwriting files that are in the GMA
+	#[serde(default)]
+	pub merge: bool,
+	/// Delete files in the destination that aren't in the GMA
+	#[serde(default)]
+	pub prune: bool,
+	/// Skip entries matching the `ignore` patterns in the GMA's addon.json, as the author would have when packing it
+	#[serde(default)]
+	pub honor_ignore: bool,
+	/// Extract entries that aren't whitelisted too, for GMAs you trust. Paths are still checked so nothing is written outside the destination
+	#[serde(default)]
+	pub skip_whitelist: bool,
+	#[serde(default)]
+	pub addon_json_style: JsonStyle,
+	/// Report the destination relative to the folder it was extracted into (e.g. `addon_name_123456790` rather than `.../garrysmod/addons/addon_name_123456790`)
+	#[serde(default)]
+	pub relative_paths: bool,
+	/// If the extraction is cancelled, delete the half-extracted destination, as long as the extraction created it.
+	/// Never applies to `ExtractDestination::Directory`, which is the user's own folder
+	#[serde(default)]
+	pub cleanup_on_cancel: bool,
+	/// Merge into the existing folder, only writing entries whose size or checksum differs from the file already there
+	#[serde(default)]
+	pub only_changed: bool,
+}
+impl ExtractOptions {
+	fn reported_path(&self, dest_path: &Path) -> PathBuf {
+		match dest_path.parent() {
+			Some(root) if self.relative_paths => dest_path.strip_prefix(root).unwrap_or(dest_path).to_owned(),
+			_ => dest_path.to_owned(),
+		}
+	}
+}
+
+/// A summary of an extraction, for scripting
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractReport {
+	pub destination: PathBuf,
+	pub files_written: usize,
+	/// Entries that weren't extracted because they aren't whitelisted, or are ignored with `ExtractOptions::honor_ignore`
+	pub files_skipped: Vec<String>,
+	/// Entries that weren't written because an identical file was already there, with `ExtractOptions::only_changed`
+	pub files_unchanged: usize,
+	/// Entries that failed to extract, and why
+	pub errors: Vec<(String, String)>,
+	/// The total size of the extracted entries, as stored in the GMA
+	pub total_bytes: u64,
+	pub duration_ms: u64,
+}
+
+#[derive(Default)]
+struct ExtractTally {
+	written: AtomicUsize,
+	bytes: AtomicU64,
+	skipped: Mutex<Vec<String>>,
+	unchanged: AtomicUsize,
+	errors: Mutex<Vec<(String, String)>>,
+}
+impl ExtractTally {
+	fn entry_extracted(&self, gma: &GMAFile, entry: &GMAEntry, result: Result<(), std::io::Error>, transaction: &Transaction) {
+		match result {
+			Ok(_) => {
+				self.written.fetch_add(1, Ordering::AcqRel);
+				self.bytes.fetch_add(entry.size, Ordering::AcqRel);
+				transaction.succeeded();
+			}
+			Err(error) => {
+				log::warn!("Failed to extract {} from {}: {}", entry.path, gma.path.display(), error);
+				transaction.failed(error.to_string(), entry.path.clone());
+				self.errors.lock().push((entry.path.clone(), error.to_string()));
+			}
+		}
+	}
+
+	fn entry_skipped(&self, entry_path: &str, transaction: &Transaction) {
+		transaction.skipped();
+		self.skipped.lock().push(entry_path.to_owned());
+	}
+
+	fn into_report(self, destination: PathBuf, started: Instant) -> ExtractReport {
+		let mut files_skipped = self.skipped.into_inner();
+		files_skipped.sort_unstable();
+
+		let mut errors = self.errors.into_inner();
+		errors.sort_unstable();
+
+		ExtractReport {
+			destination,
+			files_written: self.written.into_inner(),
+			files_skipped,
+			files_unchanged: self.unchanged.into_inner(),
+			errors,
+			total_bytes: self.bytes.into_inner(),
+			duration_ms: started.elapsed().as_millis() as u64,
+		}
+	}
+}
+
 impl ExtractDestination {
-	fn prepare<S: AsRef<str>>(self, extracted_name: S) -> PathBuf {
+	/// The path an addon with this extracted name would be extracted to, without touching anything on disk
+	pub fn resolve(&self, extracted_name: &str) -> PathBuf {
 		use ExtractDestination::*;
 
 		let push_extracted_name = |mut path: PathBuf| {
-			path.push(extracted_name.as_ref());
+			path.push(extracted_name);
 			Some(path)
 		};
 
-		let recycle_existing = !matches!(self, Directory(_));
-
-		let mut path = match self {
+		match self {
 			Temp => None,
 
-			Directory(path) => Some(path),
+			Directory(path) => Some(path.to_owned()),
 
-			Addons => app_data!().gmod_dir().map(|mut path| {
-				path.push("GarrysMod");
-				path.push("addons");
-				path.push(extracted_name.as_ref());
-				path
-			}),
+			Addons => app_data!().addons_dir().and_then(push_extracted_name),
 
 			Downloads => app_data!().downloads_dir().to_owned().and_then(push_extracted_name),
 
-			NamedDirectory(path) => push_extracted_name(path),
+			NamedDirectory(path) => push_extracted_name(path.to_owned()),
+
+			MountReady(path) => Some(path.join(mount_ready_name(extracted_name))),
+
+			Vpk(path) => Some(path.to_owned()),
 		}
-		.unwrap_or_else(|| push_extracted_name(app_data!().temp_dir().to_owned()).unwrap());
+		.unwrap_or_else(|| push_extracted_name(app_data!().temp_dir().to_owned()).unwrap())
+	}
+
+	fn is_mount_ready(&self) -> bool {
+		matches!(self, ExtractDestination::MountReady(_))
+	}
+
+	fn prepare<S: AsRef<str>>(self, extracted_name: S, merge: bool) -> PathBuf {
+		let recycle_existing = !merge && !matches!(self, ExtractDestination::Directory(_) | ExtractDestination::Vpk(_));
+
+		let mut path = self.resolve(extracted_name.as_ref());
 
 		if recycle_existing && path.exists() {
 			let success = match &app_data!().settings.read().extract_overwrite_mode {
@@ -100,18 +321,227 @@ impl ExtractDestination {
 	}
 }
 
+/// How many times to try creating an extracted file before giving up
+const ENTRY_CREATE_ATTEMPTS: u32 = 3;
+
+/// Errors that are usually something else (e.g. an antivirus scanner) briefly holding the file
+fn is_transient_io_error(error: &std::io::Error) -> bool {
+	use std::io::ErrorKind;
+
+	if matches!(error.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+		return true;
+	}
+
+	#[cfg(target_os = "windows")]
+	{
+		// ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+		matches!(error.raw_os_error(), Some(5) | Some(32) | Some(33))
+	}
+	#[cfg(not(target_os = "windows"))]
+	{
+		// EBUSY, ETXTBSY
+		matches!(error.raw_os_error(), Some(16) | Some(26))
+	}
+}
+
+/// Creates the file (and its parent directories) an entry is extracted to, retrying with a backoff on transient errors
+fn create_entry_file(entry_path: &Path) -> Result<File, std::io::Error> {
+	let mut attempt = 1;
+	loop {
+		let result = fs::create_dir_all(entry_path.with_file_name("")).and_then(|_| File::create(entry_path));
+		match result {
+			Err(error) if attempt < ENTRY_CREATE_ATTEMPTS && is_transient_io_error(&error) => {
+				log::debug!("Retrying {} after transient error: {}", entry_path.display(), error);
+				sleep_ms!(50 << attempt);
+				attempt += 1;
+			}
+			result => return result,
+		}
+	}
+}
+
+/// Narrows an extracted name down to what's safe for a folder in `garrysmod/addons`, which doesn't cope with anything outside lowercase ASCII
+fn mount_ready_name(extracted_name: &str) -> String {
+	let mut name = String::with_capacity(extracted_name.len());
+	for char in extracted_name.chars() {
+		if char.is_ascii_alphanumeric() {
+			name.push(char.to_ascii_lowercase());
+		} else if !name.is_empty() && !name.ends_with('_') {
+			name.push('_');
+		}
+	}
+
+	while name.ends_with('_') {
+		name.pop();
+	}
+
+	if name.is_empty() {
+		name.push_str("gmpublisher_extracted");
+	}
+
+	name
+}
+
+/// Joins an entry's path onto the destination, refusing absolute paths and `..` components so a malicious GMA can't write outside of it
+fn entry_dest_path(dest_path: &Path, entry_path: &str) -> Result<PathBuf, GMAError> {
+	let escapes = entry_path.starts_with(['/', '\\'])
+		|| entry_path.contains(':')
+		|| entry_path.split(['/', '\\']).any(|component| component == "..")
+		|| !Path::new(entry_path)
+			.components()
+			.all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+	if escapes {
+		log::warn!("Refusing to extract GMA entry outside of the destination: {}", entry_path);
+		Err(GMAError::FormatError)
+	} else {
+		Ok(dest_path.join(entry_path))
+	}
+}
+
+/// Writes everything to several files at once. A file that fails to write is dropped, so one bad destination doesn't stop the rest
+struct TeeWriter {
+	/// Each file's index among the destinations
+	files: Vec<(usize, File)>,
+	/// The destinations dropped after failing to write, and why
+	failed: Vec<(usize, std::io::Error)>,
+}
+impl std::io::Write for TeeWriter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		let failed = &mut self.failed;
+		self.files.retain_mut(|(i, f)| match f.write_all(buf) {
+			Ok(_) => true,
+			Err(error) => {
+				failed.push((*i, error));
+				false
+			}
+		});
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		let failed = &mut self.failed;
+		self.files.retain_mut(|(i, f)| match f.flush() {
+			Ok(_) => true,
+			Err(error) => {
+				failed.push((*i, error));
+				false
+			}
+		});
+		Ok(())
+	}
+}
+
+const XZ_MAGIC: &[u8; 6] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+
+/// How the LZMA stream of a compressed GMA is wrapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LzmaFraming {
+	/// The .xz container, which multithreaded compression writes
+	Xz,
+	/// Raw LZMA1 behind a 13-byte header, as served by the Workshop and written by older tools
+	Alone,
+}
+impl LzmaFraming {
+	fn detect(magic: &[u8]) -> LzmaFraming {
+		if magic.starts_with(XZ_MAGIC) {
+			LzmaFraming::Xz
+		} else {
+			LzmaFraming::Alone
+		}
+	}
+
+	fn other(self) -> LzmaFraming {
+		match self {
+			LzmaFraming::Xz => LzmaFraming::Alone,
+			LzmaFraming::Alone => LzmaFraming::Xz,
+		}
+	}
+
+	fn decoder(self, memlimit: u64) -> Result<xz2::stream::Stream, GMAError> {
+		match self {
+			LzmaFraming::Xz => xz2::stream::Stream::new_stream_decoder(memlimit, 0),
+			LzmaFraming::Alone => xz2::stream::Stream::new_lzma_decoder(memlimit),
+		}
+		.map_err(|err| {
+			log::warn!("LZMA error: {err:?}");
+			GMAError::LZMA
+		})
+	}
+}
+
 impl GMAFile {
-	pub fn decompress<P: AsRef<Path>>(path: P, transaction: Transaction) -> Result<GMAFile, GMAError> {
+	/// `memlimit` caps the decoder's memory usage so a crafted stream can't request a huge dictionary and OOM us
+	pub fn decompress<P: AsRef<Path>>(path: P, memlimit: u64, transaction: Transaction) -> Result<GMAFile, GMAError> {
 		main_thread_forbidden!();
 
-		let input = File::open(path.as_ref())?;
+		let modified = fs::metadata(path.as_ref()).and_then(|metadata| metadata.modified()).ok();
+		if let Some(modified) = modified {
+			let cached = DECOMPRESSED
+				.lock()
+				.get(path.as_ref())
+				.filter(|(cached_modified, _)| *cached_modified == modified)
+				.map(|(_, bytes)| bytes.clone());
+			if let Some(bytes) = cached {
+				let decompressed_size = bytes.len() as u64;
+
+				let mut gma = GMAFile::read_header(GMAReader::MemBuffer(Cursor::new(bytes)), path.as_ref())?;
+				gma.size = decompressed_size;
+				gma.compressed_source = Some(path.as_ref().to_owned());
+
+				return Ok(gma);
+			}
+		}
+
+		log::debug!("Decompressing {}", path.as_ref().display());
+
+		let framing = {
+			let mut magic = [0u8; XZ_MAGIC.len()];
+			let read = File::open(path.as_ref())?.read(&mut magic)?;
+			LzmaFraming::detect(&magic[..read])
+		};
+
+		// Older tools don't always frame the stream the way the magic suggests, so give the other decoder a go before giving up
+		let mut output = match GMAFile::decompress_framed(path.as_ref(), framing, memlimit, &transaction) {
+			Ok(output) => output,
+			Err(GMAError::LZMA) => {
+				log::debug!("Retrying {} as {:?}", path.as_ref().display(), framing.other());
+				transaction.progress_reset();
+				GMAFile::decompress_framed(path.as_ref(), framing.other(), memlimit, &transaction)?
+			}
+			Err(error) => return Err(error),
+		};
+
+		output.shrink_to_fit();
+
+		let decompressed_size = output.len() as u64;
+
+		log::debug!("Decompressed {} ({} bytes)", path.as_ref().display(), decompressed_size);
+
+		let output: ArcBytes = output.into();
+
+		if let Some(modified) = modified {
+			let mut cache = DECOMPRESSED.lock();
+			cache.insert(path.as_ref().to_owned(), (modified, output.clone()));
+			while cache.len() > DECOMPRESSED_CACHE_SIZE {
+				cache.shift_remove_index(0);
+			}
+		}
+
+		let mut gma = GMAFile::read_header(GMAReader::MemBuffer(Cursor::new(output)), path.as_ref())?;
+		gma.size = decompressed_size;
+		gma.compressed_source = Some(path.as_ref().to_owned());
+
+		Ok(gma)
+	}
+
+	/// Decodes the whole file with the decoder for `framing`. Fails with `GMAError::LZMA` if it's framed differently
+	fn decompress_framed(path: &Path, framing: LzmaFraming, memlimit: u64, transaction: &Transaction) -> Result<Vec<u8>, GMAError> {
+		let input = File::open(path)?;
 
 		let bytes_total = input.metadata().map(|metadata| metadata.len()).ok();
 
-		let lzma_decoder = xz2::stream::Stream::new_lzma_decoder(u64::MAX).map_err(|err| {
-			eprintln!("LZMA error: {err:?}");
-			GMAError::LZMA
-		})?;
+		let lzma_decoder = framing.decoder(memlimit)?;
 
 		let mut xz_decoder = xz2::read::XzDecoder::new_stream(input, lzma_decoder);
 
@@ -134,6 +564,7 @@ impl GMAFile {
 			unsafe impl<T: Read> Send for StupidlyUnsafeProgressMonitorPtr<T> {}
 
 			let xz_decoder_ptr = StupidlyUnsafeProgressMonitorPtr(&mut xz_decoder as *mut _);
+			let transaction = transaction.clone();
 			rayon::spawn(move || {
 				#[allow(clippy::redundant_locals)]
 				let xz_decoder_ptr = xz_decoder_ptr;
@@ -160,163 +591,602 @@ impl GMAFile {
 		};
 
 		if let Err(err) = result {
+			if matches!(
+				err.get_ref().and_then(|err| err.downcast_ref::<xz2::stream::Error>()),
+				Some(xz2::stream::Error::MemLimit)
+			) {
+				log::warn!("LZMA error decompressing {}: memory limit of {memlimit} bytes exceeded", path.display());
+				return Err(GMAError::LZMA);
+			}
+
 			// No idea why, but XZ always errors with "corrupt xz stream" even when the decompression succeeds.
 			// Maybe a difference in the way Gmod encoded the XZ stream?
 			// Let's just check if the file has been fully read, then naively continue.
+			// The wrong decoder can also stop early once it's buffered the whole of a small file, so check we got a GMA out of it too
 			let mut input = xz_decoder.into_inner();
-			if input.read(&mut [0u8]).ok() != Some(0) {
-				eprintln!("LZMA error: {err:#?}");
+			if input.read(&mut [0u8]).ok() != Some(0) || !output.starts_with(super::GMA_HEADER) {
+				log::warn!("LZMA error decompressing {}: {err:#?}", path.display());
 				return Err(GMAError::LZMA);
 			}
 		}
 
-		output.shrink_to_fit();
-
-		let decompressed_size = output.len() as u64;
-
-		let mut gma = GMAFile::read_header(GMAReader::MemBuffer(Cursor::new(output.into())), path)?;
-		gma.size = decompressed_size;
-
-		Ok(gma)
+		Ok(output)
 	}
 
-	fn stream_entry_bytes_with_transaction(
-		handle: &mut GMAReader,
-		entries_start: u64,
-		entry_path: &PathBuf,
-		entry: &GMAEntry,
-		transaction: &Transaction,
-	) -> Result<(), GMAError> {
-		use std::io::Write;
+	/// Extracts only the entries under `prefix` (e.g. `lua/`), with the prefix stripped from their destination paths.
+	/// Entries that aren't whitelisted are skipped
+	pub fn extract_subdir(&mut self, prefix: &str, dest: ExtractDestination, transaction: &Transaction) -> Result<PathBuf, GMAError> {
+		main_thread_forbidden!();
 
-		fs::create_dir_all(entry_path.with_file_name(""))?;
-		let f = File::create(entry_path)?;
+		self.entries()?;
 
-		handle.seek(SeekFrom::Start(entries_start + entry.index))?;
+		let prefix = {
+			let mut prefix = prefix.trim_matches('/').to_lowercase();
+			prefix.push('/');
+			prefix
+		};
 
-		let mut w = BufWriter::new(f);
-		crate::stream_bytes_with_transaction(&mut **handle, &mut w, entry.size as usize, transaction)?;
+		let dest_path = dest.prepare(&self.extracted_name, false);
+		let tally = ExtractTally::default();
 
-		w.flush()?;
+		let entries = self
+			.entries
+			.as_ref()
+			.unwrap()
+			.values()
+			.filter_map(|entry| Some((entry, entry.path.strip_prefix(&prefix)?)))
+			.filter(|(entry, _)| {
+				if whitelist::check(&entry.path) {
+					true
+				} else {
+					tally.entry_skipped(&entry.path, transaction);
+					false
+				}
+			})
+			.map(|(entry, relative_path)| Ok((entry, entry_dest_path(&dest_path, relative_path)?)))
+			.collect::<Result<_, GMAError>>()?;
 
-		Ok(())
-	}
+		let result = self.extract_sequential(entries, transaction, &tally).map(|_| dest_path);
 
-	fn stream_entry_bytes(handle: &mut GMAReader, entries_start: u64, entry_path: &PathBuf, entry: &GMAEntry) -> Result<(), GMAError> {
-		use std::io::Write;
+		if !transaction.aborted() {
+			match result {
+				Ok(ref dest_path) => transaction.finished(dest_path.to_owned()),
+				Err(ref error) => transaction.error(error.to_string(), turbonone!()),
+			}
+		}
 
-		fs::create_dir_all(entry_path.with_file_name(""))?;
-		let f = File::create(entry_path)?;
+		result
+	}
 
-		handle.seek(SeekFrom::Start(entries_start + entry.index))?;
+	/// How many bytes extracting every entry needs, and how many are free on the destination's volume.
+	/// Individually compressed entries are counted at their compressed size
+	pub fn extract_space_check(&mut self, dest: &ExtractDestination) -> Result<(u64, u64), GMAError> {
+		self.entries()?;
 
-		let mut w = BufWriter::new(f);
-		crate::stream_bytes(&mut **handle, &mut w, entry.size as usize)?;
+		let needed = self.entries.as_ref().unwrap().values().map(|entry| entry.size).sum();
 
-		w.flush()?;
+		// The destination usually doesn't exist yet
+		let dest_path = dest.resolve(&self.extracted_name);
+		let available = match dest_path.ancestors().find(|path| path.exists()) {
+			Some(path) => fs2::available_space(path)?,
+			None => return Err(GMAError::IOError),
+		};
 
-		Ok(())
+		Ok((needed, available))
 	}
-}
 
-pub trait ExtractGMAImmut {
-	fn extract(
-		&self,
-		dest: ExtractDestination,
-		transaction: &Transaction,
-		open_after_extract: bool,
-		ignore_whitelist: bool,
-	) -> Result<PathBuf, GMAError>;
-	fn extract_entry(&self, entry_path: String, transaction: &Transaction, open_after_extract: bool) -> Result<PathBuf, GMAError>;
-	fn extract_entry_with_handle(
-		&self,
-		entry_path: String,
-		transaction: &Transaction,
-		open_after_extract: bool,
-		handle: Option<GMAReader>,
-	) -> Result<PathBuf, GMAError>;
-}
-pub trait ExtractGMAMut {
-	fn extract(
+	/// Extracts the GMA to every destination at once, reading each entry only once, with a report for each destination.
+	/// Entries that aren't whitelisted are skipped, and progress counts every file written to every destination.
+	/// Only writes loose files, so `ExtractDestination::Vpk` fails with `GMAError::UnsupportedDestination`
+	pub fn extract_to_many(
 		&mut self,
-		dest: ExtractDestination,
+		dests: Vec<ExtractDestination>,
+		options: ExtractOptions,
 		transaction: &Transaction,
-		open_after_extract: bool,
-		ignore_whitelist: bool,
-	) -> Result<PathBuf, GMAError>;
-	fn extract_entry(&mut self, entry_path: String, transaction: &Transaction, open_after_extract: bool) -> Result<PathBuf, GMAError>;
-}
-impl ExtractGMAImmut for GMAFile {
-	fn extract(
+	) -> Result<Vec<ExtractReport>, GMAError> {
+		main_thread_forbidden!();
+
+		let result = self.entries().and_then(|_| self.extract_to_many_tallied(dests, &options, transaction));
+
+		if !transaction.aborted() {
+			match result {
+				Ok(ref reports) => transaction.finished(reports.iter().map(|report| report.destination.clone()).collect::<Vec<_>>()),
+				Err(ref error) => transaction.error(error.to_string(), turbonone!()),
+			}
+		}
+
+		result
+	}
+
+	fn extract_to_many_tallied(
 		&self,
-		dest: ExtractDestination,
+		dests: Vec<ExtractDestination>,
+		options: &ExtractOptions,
 		transaction: &Transaction,
-		open_after_extract: bool,
-		ignore_whitelist: bool,
-	) -> Result<PathBuf, GMAError> {
-		let result = THREAD_POOL.install(move || {
-			let dest_path = dest.prepare(&self.extracted_name);
-			let entries_start = self.pointers.entries;
-
-			let entries = self.entries.as_ref().unwrap();
-			let entries_len_f = entries.len() as f64;
-			let entries_len_i = entries.len();
+	) -> Result<Vec<ExtractReport>, GMAError> {
+		use std::io::Write;
 
-			self.read()?; // Don't waste time with the threads if the file fails to open
+		let started = Instant::now();
 
-			let i = AtomicUsize::new(0);
+		let mut dest_paths: Vec<PathBuf> = Vec::with_capacity(dests.len());
+		// Whether each destination must be mountable as soon as it's extracted
+		let mut mount_ready: Vec<bool> = Vec::with_capacity(dests.len());
+		for dest in dests {
+			if matches!(dest, ExtractDestination::Vpk(_)) {
+				return Err(GMAError::UnsupportedDestination);
+			}
 
-			let finished = |mut dest_path: PathBuf| {
-				if i.fetch_add(1, Ordering::AcqRel) > entries_len_i || transaction.aborted() {
-					return;
+			let dest_mount_ready = dest.is_mount_ready();
+			let dest_path = dest.prepare(&self.extracted_name, options.merge || options.only_changed);
+			match dest_paths.iter().position(|existing| *existing == dest_path) {
+				Some(i) => mount_ready[i] |= dest_mount_ready,
+				None => {
+					dest_paths.push(dest_path);
+					mount_ready.push(dest_mount_ready);
 				}
+			}
+		}
 
-				transaction.finished(dest_path.to_owned());
+		let tallies: Vec<ExtractTally> = dest_paths.iter().map(|_| ExtractTally::default()).collect();
 
-				if open_after_extract {
-					crate::path::open(&dest_path);
-				}
+		let ignore: Vec<String> = match self.metadata.as_ref().and_then(|metadata| metadata.ignore()) {
+			Some(ignore) if options.honor_ignore => ignore
+				.iter()
+				.map(|ignore| {
+					let mut ignore = ignore.to_owned();
+					ignore.push('\0');
+					ignore
+				})
+				.collect(),
+			_ => Vec::new(),
+		};
 
-				let metadata = self.metadata.as_ref().unwrap();
-				if let GMAMetadata::Standard { .. } = metadata {
-					if let Ok(json) = serde_json::ser::to_string_pretty(metadata) {
-						dest_path.push("addon.json");
-						if let Some(parent) = dest_path.parent() {
-							ignore! { fs::create_dir_all(parent) };
-						}
-						ignore! { fs::write(dest_path, json.as_bytes()) };
+		// Check every path before writing anything, so a malicious GMA doesn't get partially extracted
+		let mut entries = self
+			.entries
+			.as_ref()
+			.unwrap()
+			.values()
+			.filter(|entry| {
+				if (options.skip_whitelist || whitelist::check(&entry.path)) && !whitelist::is_ignored(&entry.path, &ignore) {
+					true
+				} else {
+					for tally in tallies.iter() {
+						tally.entry_skipped(&entry.path, transaction);
 					}
+					false
 				}
-			};
-
-			entries
-				.par_iter()
-				.try_for_each(|(entry_path, entry)| -> Result<(), GMAError> {
-					let mut handle = self.read()?;
-
-					if ignore_whitelist || whitelist::check(entry_path) {
-						if transaction.aborted() {
-							return Err(GMAError::Cancelled);
-						}
+			})
+			.map(|entry| {
+				let entry_dest_paths = dest_paths
+					.iter()
+					.map(|dest_path| entry_dest_path(dest_path, &entry.path))
+					.collect::<Result<Vec<_>, GMAError>>()?;
+				Ok((entry, entry_dest_paths))
+			})
+			.collect::<Result<Vec<_>, GMAError>>()?;
+
+		entries.sort_unstable_by_key(|(entry, _)| entry.index);
+
+		let writes_total_f = (entries.len() * dest_paths.len()) as f64;
+		let mut writes: usize = 0;
+
+		let mut eta = EtaEstimator::new(entries.iter().map(|(entry, _)| entry.size).sum());
+
+		let mut handle = self.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+		handle.seek(SeekFrom::Start(self.pointers.entries))?;
+
+		let mut cursor = 0;
+		for (entry, entry_dest_paths) in entries {
+			if transaction.aborted() {
+				return Err(GMAError::Cancelled);
+			}
 
-						// FIXME count errors, check if errors == number of entries, return an error instead of finished
-						ignore! { GMAFile::stream_entry_bytes(&mut handle, entries_start, &dest_path.join(entry_path), entry) };
+			let gap = entry.index - cursor;
+			if gap > SEQUENTIAL_READ_BUFFER_SIZE as u64 {
+				handle.seek(SeekFrom::Current(gap as i64))?;
+			} else if gap > 0 {
+				std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+			}
 
-						let i = i.fetch_add(1, Ordering::AcqRel) + 1;
-						transaction.progress((i as f64) / entries_len_f);
+			writes += entry_dest_paths.len();
 
-						if i == entries_len_i {
-							(finished)(dest_path.to_owned());
-						}
+			let files = entry_dest_paths
+				.into_iter()
+				.enumerate()
+				.filter(|(i, entry_dest_path)| {
+					if options.only_changed && self.unchanged_on_disk(entry, entry_dest_path) {
+						tallies[*i].unchanged.fetch_add(1, Ordering::AcqRel);
+						transaction.skipped();
+						false
 					} else {
-						transaction.error("ERR_WHITELIST", entry_path.clone()); // TODO
+						true
 					}
-
-					Ok(())
 				})
-				.map(|_| {
-					(finished)(dest_path.to_owned());
-					dest_path
+				.filter_map(|(i, entry_dest_path)| match create_entry_file(&entry_dest_path) {
+					Ok(f) => Some((i, f)),
+					Err(error) => {
+						tallies[i].entry_extracted(self, entry, Err(error), transaction);
+						None
+					}
+				})
+				.collect();
+
+			// Even if every destination failed, the entry still has to be read through to stay in step
+			let mut w = BufWriter::with_capacity(io_buffer_size(), TeeWriter { files, failed: Vec::new() });
+			if entry.compressed {
+				GMAFile::decompress_entry_bytes(&mut handle, &mut w, entry)?;
+			} else {
+				crate::stream_bytes(&mut *handle, &mut w, entry.size as usize)?;
+			}
+			let tee = w.into_inner().map_err(std::io::IntoInnerError::into_error)?;
+
+			for (i, _) in tee.files {
+				tallies[i].entry_extracted(self, entry, Ok(()), transaction);
+			}
+			for (i, error) in tee.failed {
+				tallies[i].entry_extracted(self, entry, Err(error), transaction);
+			}
+
+			cursor = entry.index + entry.size;
+
+			transaction.progress(writes as f64 / writes_total_f);
+			if let Some(secs) = eta.advance(entry.size) {
+				transaction.eta(secs);
+			}
+		}
+
+		for (dest_path, mount_ready) in dest_paths.iter().zip(mount_ready) {
+			if options.prune {
+				self.prune(dest_path);
+			}
+
+			let addon_json = match mount_ready {
+				true => self.mount_ready_addon_json(options.addon_json_style),
+				false => self.addon_json_string_styled(options.addon_json_style),
+			};
+			if let Some(ref json) = addon_json {
+				ignore! { fs::create_dir_all(dest_path) };
+				ignore! { fs::write(dest_path.join("addon.json"), json.as_bytes()) };
+			}
+		}
+
+		Ok(tallies
+			.into_iter()
+			.zip(dest_paths)
+			.map(|(tally, dest_path)| tally.into_report(options.reported_path(&dest_path), started))
+			.collect())
+	}
+
+	/// The addon.json that extracting will write, or `None` for legacy metadata (or if it hasn't been read)
+	pub fn addon_json_string(&self) -> Option<String> {
+		self.addon_json_string_styled(JsonStyle::Pretty)
+	}
+
+	pub fn addon_json_string_styled(&self, style: JsonStyle) -> Option<String> {
+		let metadata = match self.metadata.as_ref()? {
+			metadata @ GMAMetadata::Standard { .. } => metadata,
+			GMAMetadata::Legacy { .. } => return None,
+		};
+
+		match style {
+			JsonStyle::Pretty => serde_json::ser::to_string_pretty(metadata).ok(),
+			JsonStyle::Minified => serde_json::ser::to_string(metadata).ok(),
+			JsonStyle::Tabs => {
+				let mut json = Vec::new();
+				let mut serializer = serde_json::Serializer::with_formatter(&mut json, serde_json::ser::PrettyFormatter::with_indent(b"\t"));
+				metadata.serialize(&mut serializer).ok()?;
+				String::from_utf8(json).ok()
+			}
+		}
+	}
+
+	/// Like `addon_json_string_styled`, but legacy metadata gets an addon.json too, so the extracted folder always has one to mount
+	fn mount_ready_addon_json(&self, style: JsonStyle) -> Option<String> {
+		match self.metadata.as_ref()? {
+			GMAMetadata::Standard { .. } => self.addon_json_string_styled(style),
+			GMAMetadata::Legacy { title, description } => {
+				let json = serde_json::json!({ "title": title, "description": description });
+				match style {
+					JsonStyle::Minified => serde_json::ser::to_string(&json).ok(),
+					JsonStyle::Pretty | JsonStyle::Tabs => serde_json::ser::to_string_pretty(&json).ok(),
+				}
+			}
+		}
+	}
+
+	/// Sets the Workshop ID and renames a folder that was extracted without it, returning the new path
+	pub fn restamp_extracted_dir<P: AsRef<Path>>(&mut self, extracted_dir: P, id: PublishedFileId) -> Result<PathBuf, GMAError> {
+		let extracted_dir = extracted_dir.as_ref();
+
+		self.set_ws_id(id);
+
+		let restamped = extracted_dir.with_file_name(&self.extracted_name);
+		if restamped != extracted_dir {
+			fs::rename(extracted_dir, &restamped)?;
+		}
+
+		Ok(restamped)
+	}
+
+	fn stream_entry_bytes_with_transaction(
+		handle: &mut GMAReader,
+		entries_start: u64,
+		entry_path: &PathBuf,
+		entry: &GMAEntry,
+		transaction: &Transaction,
+	) -> Result<(), GMAError> {
+		use std::io::Write;
+
+		let f = create_entry_file(entry_path)?;
+
+		handle.seek(SeekFrom::Start(entries_start + entry.index))?;
+
+		let mut w = BufWriter::with_capacity(io_buffer_size(), f);
+		if entry.compressed {
+			GMAFile::decompress_entry_bytes(handle, &mut w, entry)?;
+			transaction.progress(1.);
+		} else {
+			crate::stream_bytes_with_transaction(&mut **handle, &mut w, entry.size as usize, transaction)?;
+		}
+
+		w.flush()?;
+
+		Ok(())
+	}
+
+	fn stream_entry_bytes(handle: &mut GMAReader, entries_start: u64, entry_path: &PathBuf, entry: &GMAEntry) -> Result<(), std::io::Error> {
+		handle.seek(SeekFrom::Start(entries_start + entry.index))?;
+		GMAFile::stream_entry_bytes_here(handle, entry_path, entry)
+	}
+
+	/// Streams the entry from the handle's current position, without seeking
+	fn stream_entry_bytes_here(handle: &mut GMAReader, entry_path: &PathBuf, entry: &GMAEntry) -> Result<(), std::io::Error> {
+		use std::io::Write;
+
+		let f = create_entry_file(entry_path)?;
+
+		let mut w = BufWriter::with_capacity(io_buffer_size(), f);
+		if entry.compressed {
+			GMAFile::decompress_entry_bytes(handle, &mut w, entry)?;
+		} else {
+			crate::stream_bytes(&mut **handle, &mut w, entry.size as usize)?;
+		}
+
+		w.flush()?;
+
+		Ok(())
+	}
+
+	/// Decompresses an individually compressed entry from the handle's current position, leaving the handle just past the entry
+	pub(super) fn decompress_entry_bytes<W: std::io::Write>(handle: &mut GMAReader, w: &mut W, entry: &GMAEntry) -> Result<(), std::io::Error> {
+		let stream =
+			xz2::stream::Stream::new_auto_decoder(DEFAULT_LZMA_MEMLIMIT, 0).map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+		let mut decoder = xz2::bufread::XzDecoder::new_stream((&mut **handle).take(entry.size), stream);
+		std::io::copy(&mut decoder, w)?;
+
+		// Skip anything the decoder didn't need, so sequential extraction stays in step
+		std::io::copy(&mut decoder.into_inner(), &mut std::io::sink())?;
+
+		Ok(())
+	}
+
+	/// Deletes every file under `dest_path` that isn't an entry of this GMA, apart from the addon.json we write
+	fn prune(&self, dest_path: &Path) {
+		let entries = self.entries.as_ref().unwrap();
+
+		for entry in WalkDir::new(dest_path).into_iter().filter_map(|entry| entry.ok()) {
+			if !entry.file_type().is_file() {
+				continue;
+			}
+
+			let relative_path = match entry.path().strip_prefix(dest_path) {
+				Ok(relative_path) => relative_path.to_slash_lossy().to_lowercase(),
+				Err(_) => continue,
+			};
+
+			if relative_path == "addon.json" || entries.contains_key(&relative_path) {
+				continue;
+			}
+
+			if let Err(error) = fs::remove_file(entry.path()) {
+				log::warn!("Failed to prune {}: {}", entry.path().display(), error);
+			}
+		}
+	}
+
+	/// Whether the file at `path` already has the entry's contents. Sizes are compared first, as they're free to check
+	fn unchanged_on_disk(&self, entry: &GMAEntry, path: &Path) -> bool {
+		if entry.crc == 0 {
+			return false;
+		}
+
+		let mut f = match File::open(path) {
+			Ok(f) => f,
+			Err(_) => return false,
+		};
+
+		// The size of an individually compressed entry is its compressed size
+		if !entry.compressed && f.metadata().map_or(true, |metadata| metadata.len() != entry.size) {
+			return false;
+		}
+
+		let mut hasher = BufWriter::new(self.entry_hash().hasher());
+		if std::io::copy(&mut f, &mut hasher).is_err() {
+			return false;
+		}
+
+		match hasher.into_inner() {
+			Ok(hasher) => hasher.finalize() == entry.crc,
+			Err(_) => false,
+		}
+	}
+
+	/// Extracts everything, returning the absolute destination along with the report
+	fn extract_tallied(
+		&self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<(PathBuf, ExtractReport), GMAError> {
+		let started = Instant::now();
+		let tally = ExtractTally::default();
+
+		let ignore_whitelist = ignore_whitelist || options.skip_whitelist;
+
+		let cleanup_on_cancel = options.cleanup_on_cancel && !matches!(dest, ExtractDestination::Directory(_));
+		let mount_ready = dest.is_mount_ready();
+		let vpk = matches!(dest, ExtractDestination::Vpk(_));
+		let dest_path = dest.prepare(&self.extracted_name, options.merge || options.only_changed);
+		// Anything that's still there after preparing is being merged into or overwritten, and isn't ours to delete
+		let cleanup_on_cancel = cleanup_on_cancel && !dest_path.exists();
+
+		let result = THREAD_POOL.install(|| {
+			let dest_path = dest_path.clone();
+			let entries_start = self.pointers.entries;
+
+			let entries = self.entries.as_ref().unwrap();
+
+			self.read()?; // Don't waste time with the threads if the file fails to open
+
+			let ignore: Vec<String> = match self.metadata.as_ref().and_then(|metadata| metadata.ignore()) {
+				Some(ignore) if options.honor_ignore => ignore
+					.iter()
+					.map(|ignore| {
+						let mut ignore = ignore.to_owned();
+						ignore.push('\0');
+						ignore
+					})
+					.collect(),
+				_ => Vec::new(),
+			};
+
+			// Check every path before writing anything, so a malicious GMA doesn't get partially extracted
+			let entries_dest = entries
+				.values()
+				.filter(|entry| {
+					if whitelist::is_ignored(&entry.path, &ignore) {
+						tally.entry_skipped(&entry.path, transaction);
+						false
+					} else {
+						true
+					}
+				})
+				.map(|entry| Ok((entry, entry_dest_path(&dest_path, &entry.path)?)))
+				.collect::<Result<Vec<_>, GMAError>>()?;
+
+			let entries_dest: Vec<_> = if options.only_changed {
+				entries_dest
+					.into_par_iter()
+					.filter(|(entry, entry_dest)| {
+						if self.unchanged_on_disk(entry, entry_dest) {
+							tally.unchanged.fetch_add(1, Ordering::AcqRel);
+							transaction.skipped();
+							false
+						} else {
+							true
+						}
+					})
+					.collect()
+			} else {
+				entries_dest
+			};
+
+			if vpk {
+				let addon_json = self.addon_json_string_styled(options.addon_json_style);
+				return self
+					.extract_vpk(entries_dest, &dest_path, addon_json, ignore_whitelist, transaction, &tally)
+					.map(|_| {
+						transaction.finished(options.reported_path(&dest_path));
+						if open_after_extract {
+							crate::path::open_file_location(&dest_path);
+						}
+						dest_path
+					});
+			}
+
+			let entries_len_f = entries_dest.len() as f64;
+			let entries_len_i = entries_dest.len();
+
+			let i = AtomicUsize::new(0);
+
+			let eta = Mutex::new(EtaEstimator::new(entries_dest.iter().map(|(entry, _)| entry.size).sum()));
+
+			let finished = |dest_path: PathBuf| {
+				if i.fetch_add(1, Ordering::AcqRel) > entries_len_i || transaction.aborted() {
+					return;
+				}
+
+				if options.prune {
+					self.prune(&dest_path);
+				}
+
+				// Written before finishing so the folder is complete by the time anyone hears about it
+				let addon_json = match mount_ready {
+					true => self.mount_ready_addon_json(options.addon_json_style),
+					false => self.addon_json_string_styled(options.addon_json_style),
+				};
+				if let Some(json) = addon_json {
+					ignore! { fs::create_dir_all(&dest_path) };
+					ignore! { fs::write(dest_path.join("addon.json"), json.as_bytes()) };
+				}
+
+				transaction.finished(options.reported_path(&dest_path));
+
+				if open_after_extract {
+					crate::path::open(&dest_path);
+				}
+			};
+
+			if ignore_whitelist {
+				// We're extracting everything, so there's nothing to gain from seeking around in parallel
+				return self.extract_sequential(entries_dest, transaction, &tally).map(|_| {
+					(finished)(dest_path.to_owned());
+					dest_path
+				});
+			}
+
+			entries_dest
+				.par_iter()
+				.try_for_each(|(entry, entry_dest)| -> Result<(), GMAError> {
+					let entry_path = &entry.path;
+					let mut handle = self.read()?;
+
+					if ignore_whitelist || whitelist::check(entry_path) {
+						if transaction.aborted() {
+							return Err(GMAError::Cancelled);
+						}
+
+						// FIXME count errors, check if errors == number of entries, return an error instead of finished
+						tally.entry_extracted(
+							self,
+							entry,
+							GMAFile::stream_entry_bytes(&mut handle, entries_start, entry_dest, entry),
+							transaction,
+						);
+
+						let i = i.fetch_add(1, Ordering::AcqRel) + 1;
+						transaction.progress((i as f64) / entries_len_f);
+						if let Some(secs) = eta.lock().advance(entry.size) {
+							transaction.eta(secs);
+						}
+
+						if i == entries_len_i {
+							(finished)(dest_path.to_owned());
+						}
+					} else {
+						tally.entry_skipped(entry_path, transaction);
+						transaction.error("ERR_WHITELIST", entry_path.clone()); // TODO
+					}
+
+					Ok(())
+				})
+				.map(|_| {
+					(finished)(dest_path.to_owned());
+					dest_path
 				})
 		});
 
@@ -324,9 +1194,243 @@ impl ExtractGMAImmut for GMAFile {
 			if let Err(ref error) = result {
 				transaction.error(error.to_string(), turbonone!());
 			}
+		} else if cleanup_on_cancel && matches!(result, Err(GMAError::Cancelled)) {
+			// Nothing's there if it was cancelled before the first entry was written
+			if vpk {
+				fs::remove_file(&dest_path).ok();
+			} else {
+				fs::remove_dir_all(&dest_path).ok();
+			}
 		}
 
-		result
+		result.map(|destination| {
+			let report = tally.into_report(options.reported_path(&destination), started);
+			(destination, report)
+		})
+	}
+
+	/// Extracts the given entries to their paths in on-disk order through one large buffer, so OS readahead stays effective
+	fn extract_sequential(&self, mut entries: Vec<(&GMAEntry, PathBuf)>, transaction: &Transaction, tally: &ExtractTally) -> Result<(), GMAError> {
+		let entries_len_f = entries.len() as f64;
+
+		entries.sort_unstable_by_key(|(entry, _)| entry.index);
+
+		let mut eta = EtaEstimator::new(entries.iter().map(|(entry, _)| entry.size).sum());
+
+		let mut handle = self.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+		handle.seek(SeekFrom::Start(self.pointers.entries))?;
+
+		let mut cursor = 0;
+		for (i, (entry, entry_dest_path)) in entries.into_iter().enumerate() {
+			if transaction.aborted() {
+				return Err(GMAError::Cancelled);
+			}
+
+			let gap = entry.index - cursor;
+			if gap > SEQUENTIAL_READ_BUFFER_SIZE as u64 {
+				// Only happens when extracting a subset of the entries
+				handle.seek(SeekFrom::Current(gap as i64))?;
+			} else if gap > 0 {
+				std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+			}
+
+			// FIXME count errors, check if errors == number of entries, return an error instead of finished
+			tally.entry_extracted(
+				self,
+				entry,
+				GMAFile::stream_entry_bytes_here(&mut handle, &entry_dest_path, entry),
+				transaction,
+			);
+
+			cursor = entry.index + entry.size;
+
+			transaction.progress(((i + 1) as f64) / entries_len_f);
+			if let Some(secs) = eta.advance(entry.size) {
+				transaction.eta(secs);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Packs the given entries, and the addon.json if there is one, into a VPK at `vpk_path`, reading the GMA front to back like `extract_sequential`.
+	/// Unlike loose files, a VPK is useless with an entry missing, so any failure to write one fails the whole extraction
+	fn extract_vpk(
+		&self,
+		entries: Vec<(&GMAEntry, PathBuf)>,
+		vpk_path: &Path,
+		addon_json: Option<String>,
+		ignore_whitelist: bool,
+		transaction: &Transaction,
+		tally: &ExtractTally,
+	) -> Result<(), GMAError> {
+		use std::io::Write;
+
+		let mut entries: Vec<&GMAEntry> = entries
+			.into_iter()
+			.map(|(entry, _)| entry)
+			.filter(|entry| {
+				if ignore_whitelist || whitelist::check(&entry.path) {
+					true
+				} else {
+					tally.entry_skipped(&entry.path, transaction);
+					false
+				}
+			})
+			.collect();
+		entries.sort_unstable_by_key(|entry| entry.index);
+
+		let entries_len_f = entries.len() as f64;
+		let mut eta = EtaEstimator::new(entries.iter().map(|entry| entry.size).sum());
+
+		let mut paths: Vec<String> = entries.iter().map(|entry| entry.path.clone()).collect();
+		if addon_json.is_some() {
+			paths.push("addon.json".to_string());
+		}
+
+		if let Some(parent) = vpk_path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let mut vpk = VpkWriter::new(File::create(vpk_path)?, paths)?;
+
+		let mut handle = self.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+		handle.seek(SeekFrom::Start(self.pointers.entries))?;
+
+		let mut cursor = 0;
+		for (i, entry) in entries.iter().enumerate() {
+			if transaction.aborted() {
+				return Err(GMAError::Cancelled);
+			}
+
+			let gap = entry.index - cursor;
+			if gap > SEQUENTIAL_READ_BUFFER_SIZE as u64 {
+				handle.seek(SeekFrom::Current(gap as i64))?;
+			} else if gap > 0 {
+				std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+			}
+
+			vpk.write_entry(i, |w| {
+				let mut w = BufWriter::with_capacity(io_buffer_size(), w);
+				if entry.compressed {
+					GMAFile::decompress_entry_bytes(&mut handle, &mut w, entry)?;
+				} else {
+					crate::stream_bytes(&mut *handle, &mut w, entry.size as usize)?;
+				}
+				w.flush()
+			})?;
+			tally.entry_extracted(self, entry, Ok(()), transaction);
+
+			cursor = entry.index + entry.size;
+
+			transaction.progress(((i + 1) as f64) / entries_len_f);
+			if let Some(secs) = eta.advance(entry.size) {
+				transaction.eta(secs);
+			}
+		}
+
+		if let Some(json) = addon_json {
+			vpk.write_entry(entries.len(), |w| w.write_all(json.as_bytes()))?;
+		}
+
+		vpk.finish()?;
+
+		Ok(())
+	}
+}
+
+pub trait ExtractGMAImmut {
+	fn extract(
+		&self,
+		dest: ExtractDestination,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError>;
+	fn extract_with_options(
+		&self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError>;
+	/// Like `extract_with_options`, but returns a summary of what was extracted instead of just the destination
+	fn extract_report(
+		&self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<ExtractReport, GMAError>;
+	fn extract_entry(&self, entry_path: String, transaction: &Transaction, open_after_extract: bool) -> Result<PathBuf, GMAError>;
+	fn extract_entry_with_handle(
+		&self,
+		entry_path: String,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		handle: Option<GMAReader>,
+	) -> Result<PathBuf, GMAError>;
+}
+pub trait ExtractGMAMut {
+	fn extract(
+		&mut self,
+		dest: ExtractDestination,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError>;
+	fn extract_with_options(
+		&mut self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError>;
+	fn extract_report(
+		&mut self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<ExtractReport, GMAError>;
+	fn extract_entry(&mut self, entry_path: String, transaction: &Transaction, open_after_extract: bool) -> Result<PathBuf, GMAError>;
+}
+impl ExtractGMAImmut for GMAFile {
+	fn extract(
+		&self,
+		dest: ExtractDestination,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError> {
+		ExtractGMAImmut::extract_with_options(self, dest, ExtractOptions::default(), transaction, open_after_extract, ignore_whitelist)
+	}
+
+	fn extract_with_options(
+		&self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError> {
+		self.extract_tallied(dest, options, transaction, open_after_extract, ignore_whitelist)
+			.map(|(destination, _)| destination)
+	}
+
+	fn extract_report(
+		&self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<ExtractReport, GMAError> {
+		self.extract_tallied(dest, options, transaction, open_after_extract, ignore_whitelist)
+			.map(|(_, report)| report)
 	}
 
 	fn extract_entry_with_handle(
@@ -336,10 +1440,8 @@ impl ExtractGMAImmut for GMAFile {
 		open_after_extract: bool,
 		handle: Option<GMAReader>,
 	) -> Result<PathBuf, GMAError> {
-		let mut path = app_data!().temp_dir().to_owned();
-		path.push("gmpublisher");
-		path.push(&self.extracted_name);
-		path.push(&entry_path);
+		// Same place `ExtractDestination::Temp` extracts the whole GMA to, so it ends up in the user's temp folder and gets cleaned up with it
+		let path = entry_dest_path(&ExtractDestination::Temp.resolve(&self.extracted_name), &entry_path)?;
 
 		let mut handle = match handle {
 			Some(handle) => handle,
@@ -383,22 +1485,66 @@ impl ExtractGMAMut for GMAFile {
 		transaction: &Transaction,
 		open_after_extract: bool,
 		ignore_whitelist: bool,
+	) -> Result<PathBuf, GMAError> {
+		ExtractGMAMut::extract_with_options(self, dest, ExtractOptions::default(), transaction, open_after_extract, ignore_whitelist)
+	}
+	fn extract_with_options(
+		&mut self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
 	) -> Result<PathBuf, GMAError> {
 		THREAD_POOL.install(move || {
 			self.entries()?;
-			(*self).extract(dest, transaction, open_after_extract, ignore_whitelist)
+			ExtractGMAImmut::extract_with_options(&*self, dest, options, transaction, open_after_extract, ignore_whitelist)
+		})
+	}
+	fn extract_report(
+		&mut self,
+		dest: ExtractDestination,
+		options: ExtractOptions,
+		transaction: &Transaction,
+		open_after_extract: bool,
+		ignore_whitelist: bool,
+	) -> Result<ExtractReport, GMAError> {
+		THREAD_POOL.install(move || {
+			self.entries()?;
+			(*self).extract_report(dest, options, transaction, open_after_extract, ignore_whitelist)
 		})
 	}
 	fn extract_entry(&mut self, entry_path: String, transaction: &Transaction, open_after_extract: bool) -> Result<PathBuf, GMAError> {
 		THREAD_POOL.install(move || {
-			let handle = self.entries()?;
-			(*self).extract_entry_with_handle(entry_path, transaction, open_after_extract, handle)
+			self.entries()?;
+			(*self).extract_entry_with_handle(entry_path, transaction, open_after_extract, None)
 		})
 	}
 }
 
 #[tauri::command]
-pub fn extract_gma(gma_path: PathBuf, dest: ExtractDestination) -> Option<u32> {
+pub fn extract_space_check(gma_path: PathBuf, dest: ExtractDestination) -> Result<(u64, u64), GMAError> {
+	GMAFile::open(gma_path)?.extract_space_check(&dest)
+}
+
+/// The destinations that can actually be extracted to right now, as `(variant, folder)`. Addons is left out if Garry's Mod wasn't found
+#[tauri::command]
+pub fn extract_destinations() -> Vec<(String, PathBuf)> {
+	let mut destinations = vec![("Temp".to_string(), app_data!().temp_dir().to_owned())];
+
+	if let Some(downloads) = app_data!().downloads_dir().to_owned() {
+		destinations.push(("Downloads".to_string(), downloads));
+	}
+
+	if let Some(addons) = app_data!().addons_dir() {
+		destinations.push(("Addons".to_string(), addons));
+	}
+
+	destinations
+}
+
+#[tauri::command]
+pub fn extract_gma(gma_path: PathBuf, dest: ExtractDestination, options: Option<ExtractOptions>) -> Option<u32> {
 	let mut gma = GMAFile::open(gma_path).ok()?;
 	gma.entries().ok()?;
 
@@ -406,8 +1552,941 @@ pub fn extract_gma(gma_path: PathBuf, dest: ExtractDestination) -> Option<u32> {
 	let id = transaction.id;
 
 	rayon::spawn(move || {
-		ignore! { gma.extract(dest, &transaction, true, true) };
+		ignore! { gma.extract_with_options(dest, options.unwrap_or_default(), &transaction, true, true) };
 	});
 
 	Some(id)
 }
+
+#[tauri::command]
+pub fn open_gma_entry(path: PathBuf, entry_path: String) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || {
+		let extracted = GMAFile::open(path).and_then(|mut gma| {
+			gma.entries()?;
+			ExtractGMAImmut::extract_entry(&gma, entry_path, &transaction, false)
+		});
+
+		match extracted {
+			Ok(extracted) => crate::path::open_or_reveal(extracted),
+			Err(error) => {
+				// Errors while streaming the entry have already been reported
+				if !transaction.aborted() {
+					transaction.error(error.to_string(), turbonone!());
+				}
+			}
+		}
+	});
+
+	id
+}
+
+#[test]
+fn test_extract_sequential() {
+	let gma = super::read::test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":["fun"]}"#,
+		&[
+			("lua/autorun/a.lua", b"print('a')"),
+			("materials/b.vmt", b""),
+			("lua/autorun/c.lua", b"print('c')"),
+		],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_sequential.gma");
+	fs::write(&path, &gma).unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_sequential");
+	let mut gma = GMAFile::open(&path).unwrap();
+	ExtractGMAMut::extract(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('a')");
+	assert_eq!(fs::read(dest.join("materials/b.vmt")).unwrap(), b"");
+	assert_eq!(fs::read(dest.join("lua/autorun/c.lua")).unwrap(), b"print('c')");
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_zero_length_entries() {
+	let src_path = std::env::temp_dir().join("gmpublisher_test_zero_length_entries_src");
+	fs::create_dir_all(src_path.join("gamemodes/test")).unwrap();
+	fs::write(src_path.join("gamemodes/test/test.txt"), "").unwrap();
+	fs::write(src_path.join("gamemodes/test/other.txt"), "not empty").unwrap();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_zero_length_entries.gma");
+	GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Test Gamemode".to_string(),
+			addon_type: "gamemode".to_string(),
+			description: String::new(),
+			tags: vec![],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	)
+	.create(&src_path, crate::transactions::new())
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.entries().unwrap();
+
+	let entry = &gma.entries.as_ref().unwrap()["gamemodes/test/test.txt"];
+	assert_eq!(entry.size, 0);
+	assert_eq!(entry.crc, 0);
+
+	// Not ignoring the whitelist takes the parallel, seeking path
+	let dest = std::env::temp_dir().join("gmpublisher_test_zero_length_entries");
+	ExtractGMAImmut::extract(
+		&gma,
+		ExtractDestination::Directory(dest.clone()),
+		&crate::transactions::new(),
+		false,
+		false,
+	)
+	.unwrap();
+
+	assert_eq!(fs::read(dest.join("gamemodes/test/test.txt")).unwrap(), b"");
+	assert_eq!(fs::read(dest.join("gamemodes/test/other.txt")).unwrap(), b"not empty");
+
+	fs::remove_dir_all(src_path).ok();
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_restamp_extracted_dir() {
+	let base = std::env::temp_dir().join("gmpublisher_test_restamp_extracted_dir");
+	fs::create_dir_all(&base).unwrap();
+
+	let path = base.join("addon.gma");
+	fs::write(&path, super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[])).unwrap();
+
+	let mut gma = GMAFile::open_metadata_only(&path).unwrap();
+	assert_eq!(gma.extracted_name, "test_addon");
+
+	let extracted = base.join(&gma.extracted_name);
+	fs::create_dir_all(&extracted).unwrap();
+
+	let restamped = gma.restamp_extracted_dir(&extracted, PublishedFileId(1234)).unwrap();
+	assert_eq!(restamped, base.join("test_addon_1234"));
+	assert!(restamped.is_dir());
+	assert!(!extracted.exists());
+
+	fs::remove_dir_all(base).ok();
+}
+
+#[test]
+fn test_extract_merge_prune() {
+	let gma = super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('new')")]);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_merge_prune.gma");
+	fs::write(&path, &gma).unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_merge_prune");
+	fs::create_dir_all(dest.join("lua/autorun")).unwrap();
+	fs::write(dest.join("lua/autorun/a.lua"), "print('old')").unwrap();
+	fs::write(dest.join("lua/autorun/b.lua"), "print('stale')").unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+
+	let merge = ExtractOptions {
+		merge: true,
+		..Default::default()
+	};
+	ExtractGMAMut::extract_with_options(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		merge,
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('new')");
+	assert!(dest.join("lua/autorun/b.lua").is_file());
+
+	let prune = ExtractOptions {
+		merge: true,
+		prune: true,
+		..Default::default()
+	};
+	ExtractGMAMut::extract_with_options(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		prune,
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('new')");
+	assert!(!dest.join("lua/autorun/b.lua").exists());
+	assert!(dest.join("addon.json").is_file());
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_subdir() {
+	let gma = super::read::test_gma(
+		"Test Gamemode",
+		r#"{"type":"gamemode","tags":[]}"#,
+		&[
+			("gamemodes/test/gamemode/init.lua", b"print('init')"),
+			("lua/autorun/x.lua", b"print('x')"),
+			("lua/autorun/evil.exe", b"MZ"),
+			("materials/test.vmt", b"vmt"),
+		],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_subdir.gma");
+	fs::write(&path, &gma).unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_subdir");
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.extract_subdir("lua/", ExtractDestination::Directory(dest.clone()), &crate::transactions::new())
+		.unwrap();
+
+	assert_eq!(fs::read(dest.join("autorun/x.lua")).unwrap(), b"print('x')");
+	assert!(!dest.join("autorun/evil.exe").exists());
+	assert!(!dest.join("gamemodes").exists());
+	assert!(!dest.join("materials").exists());
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_compressed_entries() {
+	use crate::NTStringWriter;
+	use byteorder::{LittleEndian, WriteBytesExt};
+	use std::io::Write;
+
+	let plain: &[u8] = b"print('plain')";
+	let compressed = {
+		let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+		encoder.write_all(b"print('compressed')").unwrap();
+		encoder.finish().unwrap()
+	};
+
+	let mut gma = Vec::new();
+	gma.extend_from_slice(super::GMA_HEADER);
+	gma.write_u8(super::GMA_VERSION_ENTRY_FLAGS).unwrap();
+	gma.write_u64::<LittleEndian>(0).unwrap();
+	gma.write_u64::<LittleEndian>(0).unwrap();
+	gma.write_u8(0).unwrap();
+	gma.write_nt_string("Test Addon").unwrap();
+	gma.write_nt_string(r#"{"type":"tool","tags":[]}"#).unwrap();
+	gma.write_nt_string("Author Name").unwrap();
+	gma.write_i32::<LittleEndian>(1).unwrap();
+	for (i, (path, contents, flags)) in [
+		("lua/autorun/a.lua", &compressed[..], super::GMA_ENTRY_FLAG_COMPRESSED),
+		("lua/autorun/b.lua", plain, 0),
+	]
+	.iter()
+	.enumerate()
+	{
+		gma.write_u32::<LittleEndian>(i as u32 + 1).unwrap();
+		gma.write_nt_string(path).unwrap();
+		gma.write_i64::<LittleEndian>(contents.len() as i64).unwrap();
+		gma.write_u32::<LittleEndian>(0).unwrap();
+		gma.write_u8(*flags).unwrap();
+	}
+	gma.write_u32::<LittleEndian>(0).unwrap();
+	gma.extend_from_slice(&compressed);
+	gma.extend_from_slice(plain);
+	gma.write_u32::<LittleEndian>(0).unwrap();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_compressed_entries.gma");
+	fs::write(&path, &gma).unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_compressed_entries");
+	let mut gma = GMAFile::open(&path).unwrap();
+	ExtractGMAMut::extract(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('compressed')");
+	assert_eq!(fs::read(dest.join("lua/autorun/b.lua")).unwrap(), plain);
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_rejects_traversal() {
+	assert!(entry_dest_path(Path::new("dest"), "lua/autorun/a.lua").is_ok());
+	assert!(entry_dest_path(Path::new("dest"), "lua/../../evil.lua").is_err());
+	assert!(entry_dest_path(Path::new("dest"), "lua\\..\\..\\evil.lua").is_err());
+	assert!(entry_dest_path(Path::new("dest"), "/evil.lua").is_err());
+	assert!(entry_dest_path(Path::new("dest"), "C:/evil.lua").is_err());
+
+	let outside = std::env::temp_dir().join("gmpublisher_test_extract_rejects_traversal_evil.lua");
+	fs::remove_file(&outside).ok();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_rejects_traversal.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), (&outside.to_string_lossy(), b"print('evil')")],
+		),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_rejects_traversal");
+	fs::remove_dir_all(&dest).ok();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert!(matches!(
+		ExtractGMAMut::extract(
+			&mut gma,
+			ExtractDestination::Directory(dest.clone()),
+			&crate::transactions::new(),
+			false,
+			true,
+		),
+		Err(GMAError::FormatError)
+	));
+
+	assert!(!outside.exists());
+	assert!(!dest.join("lua/autorun/a.lua").exists());
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_report() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_report.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("lua/autorun/b.exe", b"MZ")],
+		),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_report");
+	fs::remove_dir_all(&dest).ok();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let report = ExtractGMAMut::extract_report(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		ExtractOptions::default(),
+		&crate::transactions::new(),
+		false,
+		false,
+	)
+	.unwrap();
+
+	assert_eq!(report.destination, dest);
+	assert_eq!(report.files_written, 1);
+	assert_eq!(report.files_skipped, vec!["lua/autorun/b.exe".to_string()]);
+	assert!(report.errors.is_empty());
+	assert_eq!(report.total_bytes, 10);
+	assert!(!dest.join("lua/autorun/b.exe").exists());
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_honor_ignore() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_honor_ignore.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[],"ignore":["lua/dev/*"]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("lua/dev/b.lua", b"print('b')")],
+		),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_honor_ignore");
+	fs::remove_dir_all(&dest).ok();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let report = ExtractGMAMut::extract_report(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		ExtractOptions {
+			honor_ignore: true,
+			..Default::default()
+		},
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+
+	assert!(dest.join("lua/autorun/a.lua").is_file());
+	assert!(!dest.join("lua/dev/b.lua").exists());
+	assert_eq!(report.files_skipped, vec!["lua/dev/b.lua".to_string()]);
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_space_check() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_space_check.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("materials/b.vmt", b"")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let (needed, available) = gma
+		.extract_space_check(&ExtractDestination::Directory(
+			std::env::temp_dir().join("gmpublisher_test_extract_space_check/a/b"),
+		))
+		.unwrap();
+
+	assert_eq!(needed, 10);
+	assert!(available > 0);
+
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_create_entry_file() {
+	assert!(is_transient_io_error(&std::io::Error::from(std::io::ErrorKind::Interrupted)));
+	assert!(!is_transient_io_error(&std::io::Error::from(std::io::ErrorKind::NotFound)));
+
+	let dir = std::env::temp_dir().join("gmpublisher_test_create_entry_file");
+	fs::remove_dir_all(&dir).ok();
+
+	create_entry_file(&dir.join("lua/autorun/a.lua")).unwrap();
+	assert!(dir.join("lua/autorun/a.lua").is_file());
+
+	// A file where a directory should be isn't going to go away by itself
+	assert!(create_entry_file(&dir.join("lua/autorun/a.lua/b.lua")).is_err());
+
+	fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_extract_to_many() {
+	let gma = super::read::test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":[]}"#,
+		&[("lua/autorun/a.lua", b"print('a')"), ("materials/b.vmt", b"vmt"), ("secret.exe", b"MZ")],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_to_many.gma");
+	fs::write(&path, &gma).unwrap();
+
+	let dest_a = std::env::temp_dir().join("gmpublisher_test_extract_to_many_a");
+	let dest_b = std::env::temp_dir().join("gmpublisher_test_extract_to_many_b");
+	fs::remove_dir_all(&dest_a).ok();
+	fs::remove_dir_all(&dest_b).ok();
+
+	let dests = vec![
+		ExtractDestination::Directory(dest_a.clone()),
+		ExtractDestination::Directory(dest_b.clone()),
+		ExtractDestination::Directory(dest_a.clone()),
+	];
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let reports = gma
+		.extract_to_many(dests.clone(), ExtractOptions::default(), &crate::transactions::new())
+		.unwrap();
+	assert_eq!(
+		reports.iter().map(|report| report.destination.clone()).collect::<Vec<_>>(),
+		vec![dest_a.clone(), dest_b.clone()]
+	);
+
+	for (dest, report) in [&dest_a, &dest_b].into_iter().zip(&reports) {
+		assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('a')");
+		assert_eq!(fs::read(dest.join("materials/b.vmt")).unwrap(), b"vmt");
+		assert!(!dest.join("secret.exe").exists());
+		assert!(dest.join("addon.json").is_file());
+
+		assert_eq!(report.files_written, 2);
+		assert_eq!(report.files_skipped, vec!["secret.exe".to_string()]);
+		assert!(report.errors.is_empty());
+	}
+
+	// Everything's already there, so nothing gets rewritten
+	let reports = gma
+		.extract_to_many(
+			dests,
+			ExtractOptions {
+				only_changed: true,
+				..Default::default()
+			},
+			&crate::transactions::new(),
+		)
+		.unwrap();
+	for report in reports {
+		assert_eq!(report.files_written, 0);
+		assert_eq!(report.files_unchanged, 2);
+	}
+
+	fs::remove_dir_all(dest_a).ok();
+	fs::remove_dir_all(dest_b).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_skip_whitelist() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_skip_whitelist.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("tools/build.exe", b"MZ")],
+		),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_skip_whitelist");
+
+	for skip_whitelist in [false, true] {
+		fs::remove_dir_all(&dest).ok();
+
+		let mut gma = GMAFile::open(&path).unwrap();
+		ExtractGMAMut::extract_with_options(
+			&mut gma,
+			ExtractDestination::Directory(dest.clone()),
+			ExtractOptions {
+				skip_whitelist,
+				..Default::default()
+			},
+			&crate::transactions::new(),
+			false,
+			false,
+		)
+		.unwrap();
+
+		assert!(dest.join("lua/autorun/a.lua").is_file());
+		assert_eq!(dest.join("tools/build.exe").is_file(), skip_whitelist);
+	}
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_addon_json_style() {
+	let gma = GMAFile::new(
+		"gmpublisher_test_addon_json_style.gma",
+		GMAMetadata::Standard {
+			title: "Test Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec!["fun".to_string()],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	);
+
+	let minified = gma.addon_json_string_styled(JsonStyle::Minified).unwrap();
+	assert!(!minified.contains('\n'));
+
+	let tabs = gma.addon_json_string_styled(JsonStyle::Tabs).unwrap();
+	assert!(tabs.contains("\n\t\"title\""));
+
+	assert_eq!(gma.addon_json_string().unwrap(), gma.addon_json_string_styled(JsonStyle::Pretty).unwrap());
+	assert!(gma.addon_json_string().unwrap().contains("\n  \"title\""));
+
+	for json in [minified, tabs] {
+		assert_eq!(
+			serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+			serde_json::from_str::<serde_json::Value>(&gma.addon_json_string().unwrap()).unwrap()
+		);
+	}
+}
+
+#[test]
+fn test_extract_relative_paths() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_relative_paths.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_relative_paths");
+	let options = ExtractOptions {
+		relative_paths: true,
+		..Default::default()
+	};
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let report = ExtractGMAMut::extract_report(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		options,
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+	assert_eq!(report.destination, PathBuf::from("gmpublisher_test_extract_relative_paths"));
+
+	// The returned path is still absolute, so callers can keep using it
+	let extracted = ExtractGMAMut::extract_with_options(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		options,
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+	assert_eq!(extracted, dest);
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_eta_estimator() {
+	let start = Instant::now();
+	let mut eta = EtaEstimator::new_at(start, 1000);
+
+	// 100 bytes/sec with 900 bytes to go
+	assert_eq!(eta.advance_at(start + Duration::from_secs(1), 100), Some(9));
+	// Unchanged estimates aren't reported again
+	assert_eq!(eta.advance_at(start + Duration::from_millis(1500), 50), None);
+
+	// Once the early samples fall out of the window, only recent throughput counts
+	for secs in 3..=8 {
+		eta.advance_at(start + Duration::from_secs(secs), 50);
+	}
+	// 250 bytes over the last 5 seconds, with 550 bytes to go
+	assert_eq!(eta.eta(start + Duration::from_secs(8)), Some(11));
+
+	assert_eq!(eta.advance_at(start + Duration::from_secs(9), 200), Some(5));
+}
+
+#[test]
+fn test_extract_decompressed() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_decompressed.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let compressed = std::env::temp_dir().join("gmpublisher_test_extract_decompressed.gma.lzma");
+	GMAFile::open(&path)
+		.unwrap()
+		.compress(&compressed, super::GmaCompression::Lzma { preset: 6, threads: 1 })
+		.unwrap();
+
+	// Everything has to come from the decompressed buffer, not the compressed file at `gma.path`
+	let mut gma = GMAFile::decompress(&compressed, DEFAULT_LZMA_MEMLIMIT, crate::transactions::new()).unwrap();
+	assert!(matches!(gma.read().unwrap(), GMAReader::MemBuffer(_)));
+	assert_eq!(gma.source_path(), compressed);
+	assert_eq!(GMAFile::open(&path).unwrap().source_path(), path);
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_decompressed");
+	ExtractGMAMut::extract(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('a')");
+
+	// Recompressing it should give back the same GMA
+	let recompressed = std::env::temp_dir().join("gmpublisher_test_extract_decompressed_recompressed.gma.lzma");
+	gma.compress(&recompressed, super::GmaCompression::Lzma { preset: 6, threads: 1 })
+		.unwrap();
+	assert_eq!(fs::read(&recompressed).unwrap(), fs::read(&compressed).unwrap());
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(recompressed).ok();
+	fs::remove_file(compressed).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_only_changed() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_only_changed.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[
+				("lua/autorun/a.lua", b"print('a')"),
+				("lua/autorun/b.lua", b"print('b')"),
+				("lua/autorun/c.lua", b"print('c')"),
+			],
+		),
+	)
+	.unwrap();
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_extract_only_changed");
+	fs::remove_dir_all(&dest).ok();
+	fs::create_dir_all(dest.join("lua/autorun")).unwrap();
+	fs::write(dest.join("lua/autorun/a.lua"), b"print('a')").unwrap();
+	fs::write(dest.join("lua/autorun/b.lua"), b"print('B')").unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let report = ExtractGMAMut::extract_report(
+		&mut gma,
+		ExtractDestination::Directory(dest.clone()),
+		ExtractOptions {
+			only_changed: true,
+			..Default::default()
+		},
+		&crate::transactions::new(),
+		false,
+		false,
+	)
+	.unwrap();
+
+	assert_eq!(report.files_written, 2);
+	assert_eq!(report.files_unchanged, 1);
+	assert_eq!(fs::read(dest.join("lua/autorun/b.lua")).unwrap(), b"print('b')");
+	assert_eq!(fs::read(dest.join("lua/autorun/c.lua")).unwrap(), b"print('c')");
+
+	fs::remove_dir_all(dest).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_decompress_framing() {
+	let path = std::env::temp_dir().join("gmpublisher_test_decompress_framing.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	// One thread writes raw LZMA behind the 13-byte header, more than one writes the .xz container
+	for (threads, framing) in [(1, LzmaFraming::Alone), (2, LzmaFraming::Xz)] {
+		let compressed = std::env::temp_dir().join(format!("gmpublisher_test_decompress_framing_{}.gma.lzma", threads));
+		GMAFile::open(&path)
+			.unwrap()
+			.compress(&compressed, super::GmaCompression::Lzma { preset: 6, threads })
+			.unwrap();
+
+		assert_eq!(LzmaFraming::detect(&fs::read(&compressed).unwrap()), framing);
+
+		// The wrong decoder has to fail outright, so `decompress` knows to fall back to the other one
+		for framing in [framing, framing.other()] {
+			let result = GMAFile::decompress_framed(&compressed, framing, DEFAULT_LZMA_MEMLIMIT, &crate::transactions::new());
+			assert_eq!(result.is_ok(), framing == LzmaFraming::detect(&fs::read(&compressed).unwrap()));
+		}
+
+		let mut gma = GMAFile::decompress(&compressed, DEFAULT_LZMA_MEMLIMIT, crate::transactions::new()).unwrap();
+		assert_eq!(gma.read_entry_prefix("lua/autorun/a.lua", 64).unwrap(), b"print('a')");
+
+		fs::remove_file(compressed).ok();
+	}
+
+	let garbage = std::env::temp_dir().join("gmpublisher_test_decompress_framing_garbage.gma.lzma");
+	fs::write(&garbage, [0xFFu8; 64]).unwrap();
+	assert!(matches!(
+		GMAFile::decompress(&garbage, DEFAULT_LZMA_MEMLIMIT, crate::transactions::new()),
+		Err(GMAError::LZMA)
+	));
+
+	fs::remove_file(garbage).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_compression_ratio() {
+	let path = std::env::temp_dir().join("gmpublisher_test_compression_ratio.gma");
+	let contents = "print('hello')\n".repeat(4096);
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", contents.as_bytes())],
+		),
+	)
+	.unwrap();
+
+	assert_eq!(GMAFile::open(&path).unwrap().compression_ratio(), None);
+
+	let compressed = std::env::temp_dir().join("gmpublisher_test_compression_ratio.gma.lzma");
+	GMAFile::open(&path)
+		.unwrap()
+		.compress(&compressed, super::GmaCompression::Lzma { preset: 6, threads: 1 })
+		.unwrap();
+
+	let gma = GMAFile::decompress(&compressed, DEFAULT_LZMA_MEMLIMIT, crate::transactions::new()).unwrap();
+	let expected = fs::metadata(&path).unwrap().len() as f64 / fs::metadata(&compressed).unwrap().len() as f64;
+	assert_eq!(gma.compression_ratio(), Some(expected));
+	assert!(expected > 10.);
+
+	fs::remove_file(compressed).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_mount_ready() {
+	assert_eq!(mount_ready_name("ünïcode_addon_123"), "n_code_addon_123");
+	assert_eq!(mount_ready_name("日本"), "gmpublisher_extracted");
+
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_mount_ready.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let addons = std::env::temp_dir().join("gmpublisher_test_extract_mount_ready");
+	fs::create_dir_all(&addons).unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let dest = ExtractGMAMut::extract(
+		&mut gma,
+		ExtractDestination::MountReady(addons.clone()),
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+
+	assert_eq!(dest, addons.join("test_addon"));
+	assert!(dest.join("addon.json").is_file());
+	assert_eq!(fs::read(dest.join("lua/autorun/a.lua")).unwrap(), b"print('a')");
+
+	fs::remove_dir_all(addons).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_to_many_mount_ready_legacy() {
+	// Not JSON, so the metadata is legacy
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_to_many_mount_ready_legacy.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", "Description", &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let addons = std::env::temp_dir().join("gmpublisher_test_extract_to_many_mount_ready_legacy");
+	let dir = std::env::temp_dir().join("gmpublisher_test_extract_to_many_mount_ready_legacy_dir");
+	fs::remove_dir_all(&addons).ok();
+	fs::remove_dir_all(&dir).ok();
+	fs::create_dir_all(&addons).unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let reports = gma
+		.extract_to_many(
+			vec![ExtractDestination::MountReady(addons.clone()), ExtractDestination::Directory(dir.clone())],
+			ExtractOptions::default(),
+			&crate::transactions::new(),
+		)
+		.unwrap();
+
+	// Only the mount-ready destination needs an addon.json for Garry's Mod to load it
+	assert!(reports[0].destination.join("addon.json").is_file());
+	assert!(!dir.join("addon.json").exists());
+	assert_eq!(fs::read(dir.join("lua/autorun/a.lua")).unwrap(), b"print('a')");
+
+	fs::remove_dir_all(addons).ok();
+	fs::remove_dir_all(dir).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_vpk() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_vpk.gma");
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("materials/b.vmt", b"\"VertexLitGeneric\"")],
+		),
+	)
+	.unwrap();
+
+	let vpk_path = std::env::temp_dir().join("gmpublisher_test_extract_vpk").join("addon.vpk");
+	let mut gma = GMAFile::open(&path).unwrap();
+	let report = ExtractGMAMut::extract_report(
+		&mut gma,
+		ExtractDestination::Vpk(vpk_path.clone()),
+		ExtractOptions::default(),
+		&crate::transactions::new(),
+		false,
+		true,
+	)
+	.unwrap();
+
+	assert_eq!(report.destination, vpk_path);
+	assert_eq!(report.files_written, 2);
+
+	let vpk = fs::read(&vpk_path).unwrap();
+	assert_eq!(&vpk[0..4], &0x55AA1234u32.to_le_bytes());
+
+	// Entries in GMA order, then the addon.json
+	let tree_len = u32::from_le_bytes(vpk[8..12].try_into().unwrap()) as usize;
+	let data = &vpk[12 + tree_len..];
+	assert!(data.starts_with(b"print('a')\"VertexLitGeneric\""));
+	assert_eq!(&data[28..], gma.addon_json_string().unwrap().as_bytes());
+
+	fs::remove_dir_all(vpk_path.parent().unwrap()).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_entry_temp_dir() {
+	let path = std::env::temp_dir().join("gmpublisher_test_extract_entry_temp_dir.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	let extracted = ExtractGMAMut::extract_entry(&mut gma, "lua/autorun/a.lua".to_string(), &crate::transactions::new(), false).unwrap();
+
+	let temp = ExtractDestination::Temp.resolve(&gma.extracted_name);
+	assert_eq!(extracted, temp.join("lua/autorun/a.lua"));
+	assert_eq!(fs::read(&extracted).unwrap(), b"print('a')");
+
+	fs::remove_dir_all(temp).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_extract_options_partial() {
+	let options: ExtractOptions = serde_json::from_str(r#"{"cleanup_on_cancel":true}"#).unwrap();
+	assert!(options.cleanup_on_cancel);
+	assert!(!options.merge);
+	assert!(!options.prune);
+}
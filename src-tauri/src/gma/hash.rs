@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::{GMAFile, GMAMetadata};
+
+/// Where a non-default `EntryHash` is recorded in a standard GMA's addon.json
+const ENTRY_HASH_KEY: &str = "entry_hash";
+
+/// The algorithm behind each entry's checksum in the file table.
+/// Garry's Mod and gmad only know CRC32, so anything else is for custom GMA variants and is recorded in the metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryHash {
+	#[default]
+	Crc32,
+	XxHash32,
+}
+impl EntryHash {
+	pub fn hash(self, bytes: &[u8]) -> u32 {
+		match self {
+			EntryHash::Crc32 => crc32fast::hash(bytes),
+			EntryHash::XxHash32 => xxhash_rust::xxh32::xxh32(bytes, 0),
+		}
+	}
+
+	pub fn hasher(self) -> EntryHasher {
+		match self {
+			EntryHash::Crc32 => EntryHasher::Crc32(crc32fast::Hasher::new()),
+			EntryHash::XxHash32 => EntryHasher::XxHash32(xxhash_rust::xxh32::Xxh32::new(0)),
+		}
+	}
+}
+
+/// Streaming version of `EntryHash::hash`
+pub enum EntryHasher {
+	Crc32(crc32fast::Hasher),
+	XxHash32(xxhash_rust::xxh32::Xxh32),
+}
+impl EntryHasher {
+	pub fn update(&mut self, bytes: &[u8]) {
+		match self {
+			EntryHasher::Crc32(hasher) => hasher.update(bytes),
+			EntryHasher::XxHash32(hasher) => hasher.update(bytes),
+		}
+	}
+
+	pub fn finalize(self) -> u32 {
+		match self {
+			EntryHasher::Crc32(hasher) => hasher.finalize(),
+			EntryHasher::XxHash32(hasher) => hasher.digest(),
+		}
+	}
+}
+impl std::io::Write for EntryHasher {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.update(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+impl GMAMetadata {
+	/// Legacy GMAs have nowhere to record it, so they're always CRC32
+	pub fn entry_hash(&self) -> EntryHash {
+		match self {
+			GMAMetadata::Standard { extra, .. } => extra
+				.get(ENTRY_HASH_KEY)
+				.and_then(|entry_hash| serde_json::from_value(entry_hash.clone()).ok())
+				.unwrap_or_default(),
+			GMAMetadata::Legacy { .. } => EntryHash::Crc32,
+		}
+	}
+
+	/// Does nothing for legacy GMAs. CRC32 isn't recorded at all, so standard GMAs stay byte-identical to what gmad writes
+	pub fn set_entry_hash(&mut self, entry_hash: EntryHash) {
+		if let GMAMetadata::Standard { extra, .. } = self {
+			if entry_hash == EntryHash::Crc32 {
+				extra.remove(ENTRY_HASH_KEY);
+			} else {
+				extra.insert(ENTRY_HASH_KEY.to_string(), serde_json::json!(entry_hash));
+			}
+		}
+	}
+}
+
+impl GMAFile {
+	/// What the entries' checksums were computed with. Only known once the metadata has been read
+	pub fn entry_hash(&self) -> EntryHash {
+		self.metadata.as_ref().map(GMAMetadata::entry_hash).unwrap_or_default()
+	}
+}
+
+#[test]
+fn test_entry_hash_metadata() {
+	let mut metadata: GMAMetadata = serde_json::from_str(r#"{"title":"Test Addon","type":"tool","tags":[]}"#).unwrap();
+	assert_eq!(metadata.entry_hash(), EntryHash::Crc32);
+
+	metadata.set_entry_hash(EntryHash::XxHash32);
+	let json = serde_json::to_string(&metadata).unwrap();
+	assert!(json.contains(r#""entry_hash":"xxhash32""#));
+
+	let mut metadata: GMAMetadata = serde_json::from_str(&json).unwrap();
+	assert_eq!(metadata.entry_hash(), EntryHash::XxHash32);
+
+	metadata.set_entry_hash(EntryHash::Crc32);
+	assert!(!serde_json::to_string(&metadata).unwrap().contains("entry_hash"));
+
+	let mut hasher = EntryHash::XxHash32.hasher();
+	hasher.update(b"print(");
+	hasher.update(b"'a')");
+	assert_eq!(hasher.finalize(), EntryHash::XxHash32.hash(b"print('a')"));
+}
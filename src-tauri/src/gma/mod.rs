@@ -2,7 +2,8 @@ use std::{
 	collections::HashMap,
 	fmt::Display,
 	fs::File,
-	io::{BufReader, SeekFrom},
+	io::{BufReader, Read, SeekFrom},
+	ops::RangeInclusive,
 	path::{Path, PathBuf},
 	time::SystemTime,
 };
@@ -17,6 +18,24 @@ use crate::{game_addons::GameAddons, main_thread_forbidden, ArcBytes};
 
 const GMA_HEADER: &[u8; 4] = b"GMAD";
 
+/// How far into the file `GMAFile::open_lenient` looks for the header
+pub const LENIENT_HEADER_SCAN_LIMIT: usize = 4096;
+
+/// Our own extension of the format, which adds a flags byte after each entry's CRC. Garry's Mod can't read these
+pub const GMA_VERSION_ENTRY_FLAGS: u8 = 4;
+
+/// The entry's contents are individually xz/LZMA compressed, and its size is the compressed size
+pub const GMA_ENTRY_FLAG_COMPRESSED: u8 = 1 << 0;
+
+/// The GMA versions we can read: 1 to 3 as written by gmad, and our own extension
+pub const SUPPORTED_GMA_VERSIONS: RangeInclusive<u8> = 1..=GMA_VERSION_ENTRY_FLAGS;
+
+/// Default for `GMAFile::max_entries`, well above anything on the Workshop
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+
+/// The largest GMA `GMAFile::try_entries_blocking` will read on the calling thread
+pub const BLOCKING_ENTRIES_MAX_SIZE: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Error)]
 pub enum GMAError {
 	IOError,
@@ -25,6 +44,14 @@ pub enum GMAError {
 	EntryNotFound,
 	LZMA,
 	Cancelled,
+	DuplicateEntries(Vec<String>),
+	InvalidPath(PathBuf),
+	UnsupportedVersion(u8),
+	NoPreviewImage,
+	/// The GMA's size, which is over `BLOCKING_ENTRIES_MAX_SIZE`
+	TooLargeForBlocking(u64),
+	/// The destination can't be used for this kind of extraction
+	UnsupportedDestination,
 }
 impl Display for GMAError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -36,6 +63,43 @@ impl Display for GMAError {
 			EntryNotFound => write!(f, "ERR_GMA_ENTRY_NOT_FOUND"),
 			LZMA => write!(f, "ERR_LZMA"),
 			Cancelled => write!(f, "ERR_CANCELLED"),
+			DuplicateEntries(paths) => write!(f, "ERR_DUPLICATE_ENTRIES:{}", paths.join("\n")),
+			InvalidPath(path) => write!(f, "ERR_INVALID_PATH:{}", path.display()),
+			UnsupportedVersion(version) => write!(f, "ERR_GMA_UNSUPPORTED_VERSION:{}", version),
+			NoPreviewImage => write!(f, "ERR_GMA_NO_PREVIEW_IMAGE"),
+			TooLargeForBlocking(size) => write!(f, "ERR_GMA_TOO_LARGE_FOR_BLOCKING:{}", size),
+			UnsupportedDestination => write!(f, "ERR_UNSUPPORTED_DESTINATION"),
+		}
+	}
+}
+impl GMAError {
+	/// The error code, without the data `Display` appends to it
+	pub fn code(&self) -> &'static str {
+		use GMAError::*;
+		match self {
+			IOError => "ERR_IO_ERROR",
+			FormatError => "ERR_GMA_FORMAT_ERROR",
+			InvalidHeader => "ERR_GMA_INVALID_HEADER",
+			EntryNotFound => "ERR_GMA_ENTRY_NOT_FOUND",
+			LZMA => "ERR_LZMA",
+			Cancelled => "ERR_CANCELLED",
+			DuplicateEntries(_) => "ERR_DUPLICATE_ENTRIES",
+			InvalidPath(_) => "ERR_INVALID_PATH",
+			UnsupportedVersion(_) => "ERR_GMA_UNSUPPORTED_VERSION",
+			NoPreviewImage => "ERR_GMA_NO_PREVIEW_IMAGE",
+			TooLargeForBlocking(_) => "ERR_GMA_TOO_LARGE_FOR_BLOCKING",
+			UnsupportedDestination => "ERR_UNSUPPORTED_DESTINATION",
+		}
+	}
+
+	pub fn data(&self) -> serde_json::Value {
+		use GMAError::*;
+		match self {
+			DuplicateEntries(paths) => json!(paths),
+			InvalidPath(path) => json!(path),
+			UnsupportedVersion(version) => json!(version),
+			TooLargeForBlocking(size) => json!(size),
+			_ => serde_json::Value::Null,
 		}
 	}
 }
@@ -45,12 +109,54 @@ impl From<std::io::Error> for GMAError {
 	}
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GmaFormat {
+	Uncompressed,
+	/// LZMA "alone" stream as served by the Workshop, or an .xz stream. Open with `GMAFile::decompress`
+	Lzma,
+	Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GmaCompression {
+	#[default]
+	None,
+	/// `preset` is the xz preset, 0-9.
+	///
+	/// liblzma can only multithread the .xz container, so with more than one thread that's what gets written instead of an LZMA "alone" stream.
+	/// Both can be opened with `GMAFile::decompress`, but single-threaded output is deterministic and matches what the Workshop serves.
+	Lzma { preset: u32, threads: u32 },
+}
+
+/// Byte offsets into the GMA. Each one is 0 until the part before it has been read
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct GMAFilePointers {
+	header: u64,
 	metadata: u64,
 	entries: u64,
 	entries_list: u64,
 }
+impl GMAFilePointers {
+	/// Where the `GMAD` magic starts. Always 0 unless the GMA was opened with `GMAFile::open_lenient`
+	pub fn header(&self) -> u64 {
+		self.header
+	}
+
+	/// Where the metadata starts, just after the format version. Set when the header is read
+	pub fn metadata(&self) -> u64 {
+		self.metadata
+	}
+
+	/// Where the entries table starts. Set by `GMAFile::metadata`
+	pub fn entries_list(&self) -> u64 {
+		self.entries_list
+	}
+
+	/// Where the entries' contents start, just after the entries table. Set by `GMAFile::entries`
+	pub fn entries(&self) -> u64 {
+		self.entries
+	}
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -62,9 +168,14 @@ pub enum GMAMetadata {
 		#[serde(rename = "type")]
 		addon_type: String,
 		#[serde(default)]
+		description: String,
+		#[serde(default)]
 		tags: Vec<String>,
 		#[serde(default)]
 		ignore: Vec<String>,
+		/// Keys we don't know about (e.g. from other toolchains), kept so they survive a round trip
+		#[serde(flatten)]
+		extra: serde_json::Map<String, serde_json::Value>,
 	},
 	Legacy {
 		title: String,
@@ -80,6 +191,14 @@ impl GMAMetadata {
 		.as_str()
 	}
 
+	pub fn description(&self) -> &str {
+		match &self {
+			GMAMetadata::Standard { description, .. } => description,
+			GMAMetadata::Legacy { description, .. } => description,
+		}
+		.as_str()
+	}
+
 	pub fn addon_type(&self) -> Option<&str> {
 		match &self {
 			GMAMetadata::Standard { addon_type, .. } => Some(addon_type.as_str()),
@@ -110,6 +229,9 @@ pub struct GMAEntry {
 
 	#[serde(skip)]
 	pub index: u64,
+
+	#[serde(skip)]
+	pub compressed: bool,
 }
 
 #[derive(Clone, Serialize)]
@@ -121,9 +243,12 @@ pub struct GMAFile {
 
 	pub id: Option<PublishedFileId>,
 
-	#[serde(flatten)]
+	#[serde(flatten, serialize_with = "serialize_metadata_nested")]
 	pub metadata: Option<GMAMetadata>,
 
+	/// Game content the addon says it needs (e.g. `cstrike`), read along with the metadata. Almost always empty
+	pub required_content: Vec<String>,
+
 	pub entries: Option<HashMap<String, GMAEntry>>,
 
 	#[serde(skip)]
@@ -137,8 +262,27 @@ pub struct GMAFile {
 	#[serde(skip)]
 	pub modified: Option<u64>,
 
+	/// When the GMA was packed, in seconds since the Unix epoch, as written in its header. Set by `GMAFile::metadata`
+	#[serde(skip)]
+	pub timestamp: Option<u64>,
+
+	/// The SteamID64 written in the header as the author, which is usually 0. Set by `GMAFile::metadata`
+	#[serde(skip)]
+	pub author: Option<u64>,
+
 	#[serde(skip)]
 	pub membuffer: Option<ArcBytes>,
+
+	/// The compressed .gma this was decompressed from, which stays put even if `path` is pointed somewhere else
+	#[serde(skip)]
+	pub compressed_source: Option<PathBuf>,
+
+	#[serde(skip)]
+	pub reader_source: Option<GMAReaderSource>,
+
+	/// Reading the entries table fails with `GMAError::FormatError` if it has more entries than this, so a corrupt table can't make us loop or allocate forever
+	#[serde(skip)]
+	pub max_entries: usize,
 }
 impl std::fmt::Debug for GMAFile {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -147,11 +291,14 @@ impl std::fmt::Debug for GMAFile {
 			.field("size", &self.size)
 			.field("id", &self.id)
 			.field("metadata", &self.metadata)
+			.field("required_content", &self.required_content)
 			.field("entries", &self.entries)
 			.field("pointers", &self.pointers)
 			.field("version", &self.version)
 			.field("extracted_name", &self.extracted_name)
 			.field("modified", &self.modified)
+			.field("timestamp", &self.timestamp)
+			.field("compressed_source", &self.compressed_source)
 			.finish()
 	}
 }
@@ -173,18 +320,30 @@ impl Ord for GMAFile {
 }
 
 impl GMAFile {
-	fn read_header<P: AsRef<Path>>(mut f: GMAReader, path: P) -> Result<GMAFile, GMAError> {
+	fn read_header<P: AsRef<Path>>(f: GMAReader, path: P) -> Result<GMAFile, GMAError> {
+		GMAFile::read_header_with(f, path, false)
+	}
+
+	/// In lenient mode, skips up to `LENIENT_HEADER_SCAN_LIMIT` bytes of junk (e.g. a BOM) before the header.
+	/// Every pointer is an absolute position in the file, so they all account for the skipped bytes
+	fn read_header_with<P: AsRef<Path>>(mut f: GMAReader, path: P, lenient: bool) -> Result<GMAFile, GMAError> {
 		let mut gma = GMAFile {
 			size: path.as_ref().metadata().map(|metadata| metadata.len()).unwrap_or(0),
 			path: path.as_ref().to_owned(),
 			id: None,
 			metadata: None,
+			required_content: Vec::new(),
 			entries: None,
 			pointers: GMAFilePointers::default(),
 			version: 0,
 			extracted_name: String::new(),
 			modified: None,
+			timestamp: None,
+			author: None,
 			membuffer: None,
+			compressed_source: None,
+			reader_source: None,
+			max_entries: DEFAULT_MAX_ENTRIES,
 		};
 
 		if gma.size == 0 {
@@ -193,13 +352,38 @@ impl GMAFile {
 			}
 		}
 
-		let mut header_buf = [0; 4];
-		f.read_exact(&mut header_buf).map_err(|_| GMAError::InvalidHeader)?;
-		if &header_buf != GMA_HEADER {
-			return Err(GMAError::InvalidHeader);
+		if lenient {
+			let mut scan = Vec::with_capacity(LENIENT_HEADER_SCAN_LIMIT);
+			(&mut *f).take(LENIENT_HEADER_SCAN_LIMIT as u64).read_to_end(&mut scan)?;
+
+			match scan.windows(GMA_HEADER.len()).position(|window| window == GMA_HEADER) {
+				Some(offset) => {
+					if offset > 0 {
+						log::debug!("Skipped {} bytes before the GMA header: {}", offset, gma.path.display());
+					}
+					gma.pointers.header = offset as u64;
+					f.seek(SeekFrom::Start((offset + GMA_HEADER.len()) as u64))?;
+				}
+				None => {
+					log::warn!("Invalid GMA header: {}", gma.path.display());
+					return Err(GMAError::InvalidHeader);
+				}
+			}
+		} else {
+			let mut header_buf = [0; 4];
+			if f.read_exact(&mut header_buf).is_err() || &header_buf != GMA_HEADER {
+				log::warn!("Invalid GMA header: {}", gma.path.display());
+				return Err(GMAError::InvalidHeader);
+			}
 		}
 
 		gma.version = f.read_u8()?;
+		if !SUPPORTED_GMA_VERSIONS.contains(&gma.version) {
+			log::warn!("Unsupported GMA version {}: {}", gma.version, gma.path.display());
+			return Err(GMAError::UnsupportedVersion(gma.version));
+		}
+
+		log::debug!("Read GMA header: {} (version {})", gma.path.display(), gma.version);
 
 		gma.pointers.metadata = f.seek(SeekFrom::Current(0))?;
 
@@ -212,11 +396,142 @@ impl GMAFile {
 		Ok(gma)
 	}
 
+	pub fn new<P: AsRef<Path>>(path: P, metadata: GMAMetadata) -> GMAFile {
+		GMAFile {
+			path: path.as_ref().to_owned(),
+			size: 0,
+			id: None,
+			version: match metadata {
+				GMAMetadata::Standard { .. } => 3,
+				GMAMetadata::Legacy { .. } => 1,
+			},
+			metadata: Some(metadata),
+			required_content: Vec::new(),
+			entries: None,
+			pointers: GMAFilePointers::default(),
+			extracted_name: String::new(),
+			modified: None,
+			timestamp: None,
+			author: None,
+			membuffer: None,
+			compressed_source: None,
+			reader_source: None,
+			max_entries: DEFAULT_MAX_ENTRIES,
+		}
+	}
+
+	pub fn pointers(&self) -> &GMAFilePointers {
+		&self.pointers
+	}
+
+	/// Where the GMA originally came from: the compressed file if it was opened with `decompress`, otherwise `path`
+	pub fn source_path(&self) -> &Path {
+		self.compressed_source.as_deref().unwrap_or(&self.path)
+	}
+
+	/// How many times smaller the compressed file is than the GMA inside it. `None` unless it was opened with `decompress`.
+	/// The whole file is compressed as one stream, so there's no telling how well any single entry compressed
+	pub fn compression_ratio(&self) -> Option<f64> {
+		let compressed_size = self.compressed_source.as_ref()?.metadata().ok()?.len();
+		if compressed_size == 0 {
+			None
+		} else {
+			Some(self.size as f64 / compressed_size as f64)
+		}
+	}
+
+	/// Whether the GMA was packed or last modified on disk after `time`. Uses `modified` if it's set, otherwise asks the filesystem
+	pub fn is_newer_than(&self, time: SystemTime) -> bool {
+		let time = time.duration_since(SystemTime::UNIX_EPOCH).map(|dur| dur.as_secs()).unwrap_or(0);
+
+		let modified = self.modified.or_else(|| {
+			self.path
+				.metadata()
+				.and_then(|metadata| metadata.modified())
+				.ok()
+				.and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+				.map(|dur| dur.as_secs())
+		});
+
+		self.timestamp.into_iter().chain(modified).any(|newer| newer > time)
+	}
+
+	/// Sniffs the first few bytes to tell whether to `open` or `decompress` the file
+	pub fn peek_format<P: AsRef<Path>>(path: P) -> Result<GmaFormat, GMAError> {
+		let mut f = File::open(path.as_ref())?;
+
+		let mut header = [0u8; 13];
+		let mut read = 0;
+		while read < header.len() {
+			match f.read(&mut header[read..])? {
+				0 => break,
+				n => read += n,
+			}
+		}
+		let header = &header[..read];
+
+		if header.starts_with(GMA_HEADER) {
+			return Ok(GmaFormat::Uncompressed);
+		}
+
+		if header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+			return Ok(GmaFormat::Lzma);
+		}
+
+		if header.len() == 13 {
+			// props byte, u32 dictionary size, u64 uncompressed size
+			// Same sanity checks liblzma uses when it has to guess whether something is a .lzma file
+			let props = header[0];
+			let dict_size = u32::from_le_bytes(header[1..5].try_into().unwrap());
+			let uncompressed_size = u64::from_le_bytes(header[5..13].try_into().unwrap());
+
+			let dict_size_valid = {
+				let mut d = dict_size.saturating_sub(1);
+				d |= d >> 2;
+				d |= d >> 3;
+				d |= d >> 4;
+				d |= d >> 8;
+				d |= d >> 16;
+				d.wrapping_add(1) == dict_size
+			};
+
+			if props <= (4 * 5 + 4) * 9 + 8 && dict_size_valid && (uncompressed_size == u64::MAX || uncompressed_size < (1 << 38)) {
+				return Ok(GmaFormat::Lzma);
+			}
+		}
+
+		Ok(GmaFormat::Unknown)
+	}
+
+	/// Only reads the header. Metadata and entries are read lazily by `metadata()` and `entries()`
 	pub fn open<P: AsRef<Path>>(path: P) -> Result<GMAFile, GMAError> {
 		main_thread_forbidden!();
 		GMAFile::read_header(GMAReader::Disk(BufReader::new(File::open(path.as_ref())?)), path)
 	}
 
+	/// Like `open`, but tolerates junk bytes before the header, such as a BOM some tools prepend.
+	/// Only use this when the user asks for it, as it can mask genuinely broken files
+	pub fn open_lenient<P: AsRef<Path>>(path: P) -> Result<GMAFile, GMAError> {
+		main_thread_forbidden!();
+		GMAFile::read_header_with(GMAReader::Disk(BufReader::new(File::open(path.as_ref())?)), path, true)
+	}
+
+	/// Like `open`, but reads from a handle that's already open, and keeps reading from it rather than reopening `path`.
+	/// `path` is still used for the size and extracted name
+	pub fn open_file(file: File, path: PathBuf) -> Result<GMAFile, GMAError> {
+		main_thread_forbidden!();
+		GMAFile::open_with_source(path, read::file_reader_source(file))
+	}
+
+	/// Reads the header and metadata, stopping before the entries table. This is the cheapest way to scan a folder of GMAs
+	pub fn open_metadata_only<P: AsRef<Path>>(path: P) -> Result<GMAFile, GMAError> {
+		let mut gma = GMAFile::open(path)?;
+		gma.metadata()?;
+		Ok(gma)
+	}
+
+	/// Call this before extracting, so the extracted folder is named `title_id`.
+	/// If the GMA was already extracted without the ID, use `restamp_extracted_dir` instead.
 	pub fn set_ws_id(&mut self, id: PublishedFileId) {
 		let compute = self.id.is_some() || self.metadata.is_some();
 
@@ -292,6 +607,44 @@ impl GMAFile {
 	}
 }
 
+/// Flattens the metadata into the `GMAFile`, except for the unknown addon.json keys, which are nested under `extra`
+/// so they can't collide with the `GMAFile`'s own fields
+fn serialize_metadata_nested<S>(metadata: &Option<GMAMetadata>, serializer: S) -> Result<S::Ok, S::Error>
+where
+	S: serde::Serializer,
+{
+	use serde::ser::SerializeMap;
+
+	match metadata {
+		Some(GMAMetadata::Standard {
+			title,
+			addon_type,
+			description,
+			tags,
+			ignore,
+			extra,
+		}) => {
+			let mut map = serializer.serialize_map(None)?;
+			map.serialize_entry("title", title)?;
+			map.serialize_entry("type", addon_type)?;
+			map.serialize_entry("description", description)?;
+			map.serialize_entry("tags", tags)?;
+			map.serialize_entry("ignore", ignore)?;
+			if !extra.is_empty() {
+				map.serialize_entry("extra", extra)?;
+			}
+			map.end()
+		}
+		Some(GMAMetadata::Legacy { title, description }) => {
+			let mut map = serializer.serialize_map(None)?;
+			map.serialize_entry("title", title)?;
+			map.serialize_entry("description", description)?;
+			map.end()
+		}
+		None => serializer.serialize_none(),
+	}
+}
+
 fn serde_canonicalize<S>(path: &PathBuf, serializer: S) -> Result<S::Ok, S::Error>
 where
 	S: serde::Serializer,
@@ -315,3 +668,119 @@ pub mod write;
 pub use write::*;
 
 pub mod preview;
+
+pub mod tags;
+pub use tags::*;
+
+pub mod sidecar;
+
+pub mod tree;
+pub use tree::*;
+
+pub mod verify;
+pub use verify::*;
+
+pub mod diff;
+pub use diff::*;
+
+pub mod hash;
+pub use hash::*;
+
+pub mod vpk;
+
+#[test]
+fn test_peek_format() {
+	let dir = std::env::temp_dir();
+
+	let uncompressed = dir.join("gmpublisher_test_peek_format.gma");
+	std::fs::write(&uncompressed, read::test_gma("Test Addon", "Description", &[])).unwrap();
+	assert_eq!(GMAFile::peek_format(&uncompressed).unwrap(), GmaFormat::Uncompressed);
+
+	// lc=3 lp=0 pb=2, 8 MiB dictionary, unknown uncompressed size
+	let lzma = dir.join("gmpublisher_test_peek_format.gma.lzma");
+	std::fs::write(&lzma, [0x5D, 0x00, 0x00, 0x80, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).unwrap();
+	assert_eq!(GMAFile::peek_format(&lzma).unwrap(), GmaFormat::Lzma);
+
+	let unknown = dir.join("gmpublisher_test_peek_format.txt");
+	std::fs::write(&unknown, b"hello").unwrap();
+	assert_eq!(GMAFile::peek_format(&unknown).unwrap(), GmaFormat::Unknown);
+
+	std::fs::remove_file(uncompressed).ok();
+	std::fs::remove_file(lzma).ok();
+	std::fs::remove_file(unknown).ok();
+}
+
+#[test]
+fn test_serialize_extra_metadata_nested() {
+	let mut extra = serde_json::Map::new();
+	extra.insert("path".to_string(), serde_json::json!("evil"));
+	extra.insert("logo".to_string(), serde_json::json!("logo.png"));
+
+	let path = std::env::temp_dir().join("gmpublisher_test_serialize_extra_metadata_nested.gma");
+	let gma = GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Test Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec!["fun".to_string()],
+			ignore: Vec::new(),
+			extra,
+		},
+	);
+
+	let json = serde_json::to_value(&gma).unwrap();
+	assert_eq!(json["path"], serde_json::json!(path));
+	assert_eq!(json["title"], "Test Addon");
+	assert_eq!(json["type"], "tool");
+	assert_eq!(json["extra"]["path"], "evil");
+	assert_eq!(json["extra"]["logo"], "logo.png");
+	assert!(json.get("logo").is_none());
+
+	// The addon.json itself still gets the keys back at the top level
+	let metadata = serde_json::to_value(gma.metadata.as_ref().unwrap()).unwrap();
+	assert_eq!(metadata["path"], "evil");
+}
+
+#[test]
+fn test_pointers() {
+	let gma = read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_pointers.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	let mut opened = GMAFile::open(&path).unwrap();
+	assert_eq!(opened.pointers().metadata(), 5);
+	assert_eq!(opened.pointers().entries_list(), 0);
+
+	opened.entries().unwrap();
+
+	// The table holds one entry and its terminator, then the contents and the trailing CRC follow
+	let entries_list = opened.pointers().entries_list();
+	assert_eq!(&gma[entries_list as usize..entries_list as usize + 4], &1u32.to_le_bytes());
+	assert_eq!(opened.pointers().entries() as usize, gma.len() - b"print('a')".len() - 4);
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_is_newer_than() {
+	use std::time::Duration;
+
+	let path = std::env::temp_dir().join("gmpublisher_test_is_newer_than.gma");
+	std::fs::write(&path, read::test_gma("Test Addon", "Description", &[])).unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.metadata().unwrap();
+	gma.modified = Some(1000);
+
+	// The test GMA's header timestamp is 0
+	assert_eq!(gma.timestamp, Some(0));
+	assert!(gma.is_newer_than(SystemTime::UNIX_EPOCH + Duration::from_secs(999)));
+	assert!(!gma.is_newer_than(SystemTime::UNIX_EPOCH + Duration::from_secs(1000)));
+
+	gma.timestamp = Some(2000);
+	assert!(gma.is_newer_than(SystemTime::UNIX_EPOCH + Duration::from_secs(1500)));
+
+	std::fs::remove_file(path).ok();
+}
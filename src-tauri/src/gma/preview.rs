@@ -1,12 +1,69 @@
 use std::{path::PathBuf, sync::Arc};
 
-use super::{extract::ExtractGMAImmut, ExtractDestination, GMAEntry, GMAError, GMAFile};
+use super::{extract::ExtractGMAImmut, ExtractDestination, GMAEntry, GMAError, GMAFile, GmaFormat, TreeNode, DEFAULT_LZMA_MEMLIMIT};
+use image::{GenericImageView, ImageFormat};
 use parking_lot::Mutex;
 
 lazy_static! {
 	static ref PREVIEW_GMA: Mutex<Option<Arc<GMAFile>>> = Mutex::new(None);
 }
 
+/// Images smaller than this on either side are icons or UI bits, not something worth showing on the Workshop
+const GENERATED_PREVIEW_MIN_SIZE: u32 = 64;
+
+impl GMAFile {
+	/// Picks a representative image out of the GMA and writes it to a temporary PNG that can be used as a `WorkshopIcon`.
+	///
+	/// Map thumbnails (`maps/thumb/*.png`) are preferred, then the largest image entry that decodes.
+	/// VTFs aren't considered, as `image` can't decode them
+	pub fn generate_preview(&mut self) -> Result<PathBuf, GMAError> {
+		main_thread_forbidden!();
+
+		self.entries()?;
+
+		let mut candidates: Vec<(&str, ImageFormat, u64, bool)> = self
+			.entries
+			.as_ref()
+			.unwrap()
+			.values()
+			.filter_map(|entry| {
+				let format = ImageFormat::from_path(&entry.path).ok()?;
+				if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tga) {
+					return None;
+				}
+				let map_thumbnail = entry.path.starts_with("maps/thumb/") || (entry.path.starts_with("maps/") && !entry.path[5..].contains('/'));
+				Some((entry.path.as_str(), format, entry.size, map_thumbnail))
+			})
+			.collect();
+
+		candidates.sort_unstable_by(|a, b| b.3.cmp(&a.3).then(b.2.cmp(&a.2)).then(a.0.cmp(b.0)));
+		let candidates: Vec<(String, ImageFormat)> = candidates.into_iter().map(|(path, format, ..)| (path.to_owned(), format)).collect();
+
+		for (entry_path, format) in candidates {
+			let contents = self.read_entry_prefix(&entry_path, usize::MAX)?;
+
+			let image = match image::load_from_memory_with_format(&contents, format) {
+				Ok(image) => image,
+				Err(error) => {
+					log::debug!("Skipping {} as a preview: {}", entry_path, error);
+					continue;
+				}
+			};
+
+			let (width, height) = image.dimensions();
+			if width < GENERATED_PREVIEW_MIN_SIZE || height < GENERATED_PREVIEW_MIN_SIZE {
+				continue;
+			}
+
+			let path = std::env::temp_dir().join(format!("gmpublisher_preview_{}.png", self.extracted_name));
+			image.save_with_format(&path, ImageFormat::Png).map_err(|_| GMAError::IOError)?;
+			return Ok(path);
+		}
+
+		Err(GMAError::NoPreviewImage)
+	}
+}
+
 #[tauri::command]
 pub fn preview_gma(path: Option<PathBuf>) -> Result<Option<Vec<GMAEntry>>, GMAError> {
 	if let Some(path) = path {
@@ -14,18 +71,58 @@ pub fn preview_gma(path: Option<PathBuf>) -> Result<Option<Vec<GMAEntry>>, GMAEr
 
 		let mut gma = GMAFile::open(path)?;
 		gma.entries()?;
-		*lock = Some(Arc::new(gma));
 
-		let mut entries: Vec<GMAEntry> = lock.as_ref().unwrap().entries.as_ref().unwrap().values().cloned().collect();
-		entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+		let entries = gma.entries_detailed();
+		*lock = Some(Arc::new(gma));
 
-		Ok(Some(entries))
+		Ok(entries)
 	} else {
 		*PREVIEW_GMA.lock() = None;
 		Ok(None)
 	}
 }
 
+#[tauri::command]
+pub fn preview_compression_ratio(gma_path: PathBuf) -> Result<Option<f64>, GMAError> {
+	if GMAFile::peek_format(&gma_path)? != GmaFormat::Lzma {
+		return Ok(None);
+	}
+
+	// Nobody is listening for its progress, it's only needed to decompress
+	let transaction = transaction!();
+	let gma = GMAFile::decompress(gma_path, DEFAULT_LZMA_MEMLIMIT, transaction.clone());
+	transaction.cancel();
+
+	Ok(gma?.compression_ratio())
+}
+
+#[tauri::command]
+pub fn generate_gma_preview(gma_path: PathBuf) -> Result<PathBuf, GMAError> {
+	GMAFile::open(gma_path)?.generate_preview()
+}
+
+#[tauri::command]
+pub fn preview_entry_tree(gma_path: PathBuf) -> Result<TreeNode, GMAError> {
+	GMAFile::open(gma_path)?.entry_tree()
+}
+
+#[tauri::command]
+pub fn preview_addon_json(gma_path: PathBuf) -> Result<Option<String>, GMAError> {
+	Ok(GMAFile::open_metadata_only(gma_path)?.addon_json_string())
+}
+
+/// Workshop tags the GMA's entries suggest, for the UI to compare with the ones it declares
+#[tauri::command]
+pub fn preview_suggested_tags(gma_path: PathBuf) -> Result<Vec<String>, GMAError> {
+	Ok(GMAFile::open(gma_path)?.suggest_tags())
+}
+
+/// Where the previewed GMA would be extracted to, so the UI can show it before extracting
+#[tauri::command]
+pub fn preview_extract_destination(dest: ExtractDestination) -> Option<PathBuf> {
+	PREVIEW_GMA.lock().as_ref().map(|gma| dest.resolve(&gma.extracted_name))
+}
+
 #[tauri::command]
 pub fn extract_preview_entry(gma_path: PathBuf, entry_path: String) -> Option<u32> {
 	let mut lock = PREVIEW_GMA.lock();
@@ -73,3 +170,43 @@ pub fn extract_preview_gma(gma_path: PathBuf, dest: ExtractDestination) -> Optio
 		None
 	}
 }
+
+#[test]
+fn test_generate_preview() {
+	let mut icon = Vec::new();
+	image::DynamicImage::new_rgb8(128, 128)
+		.write_to(&mut icon, image::ImageOutputFormat::Png)
+		.unwrap();
+	let mut thumbnail = Vec::new();
+	image::DynamicImage::new_rgb8(96, 96)
+		.write_to(&mut thumbnail, image::ImageOutputFormat::Png)
+		.unwrap();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_generate_preview.gma");
+
+	std::fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"map","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+	assert!(matches!(GMAFile::open(&path).unwrap().generate_preview(), Err(GMAError::NoPreviewImage)));
+
+	std::fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"map","tags":[]}"#,
+			&[
+				("materials/test/icon.png", icon.as_slice()),
+				("maps/thumb/gm_test.png", thumbnail.as_slice()),
+			],
+		),
+	)
+	.unwrap();
+
+	let preview = GMAFile::open(&path).unwrap().generate_preview().unwrap();
+	assert_eq!(image::open(&preview).unwrap().dimensions(), (96, 96));
+
+	std::fs::remove_file(preview).ok();
+	std::fs::remove_file(path).ok();
+}
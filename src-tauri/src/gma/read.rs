@@ -1,10 +1,17 @@
 use std::{
 	collections::HashMap,
 	fs::File,
-	io::{BufReader, Cursor, SeekFrom},
+	io::{BufReader, Cursor, Read, Seek, SeekFrom},
+	ops::RangeInclusive,
+	path::Path,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
 };
 
 use byteorder::{LittleEndian, ReadBytesExt};
+use parking_lot::Mutex;
 
 use crate::{ArcBytes, NTStringReader};
 
@@ -16,9 +23,37 @@ macro_rules! safe_read {
 	};
 }
 
+/// Same as `std::io::BufReader`'s default
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// What `set_io_buffer_size` clamps to (4 KiB to 16 MiB)
+pub const IO_BUFFER_SIZE_RANGE: RangeInclusive<usize> = 4 * 1024..=16 * 1024 * 1024;
+
+static IO_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_BUF_SIZE);
+
+/// Sets the buffer size for reading GMAs and writing GMAs and extracted files. `None` goes back to the default of 8 KiB
+pub fn set_io_buffer_size(size: Option<usize>) {
+	let size = size.map_or(DEFAULT_BUF_SIZE, |size| {
+		size.clamp(*IO_BUFFER_SIZE_RANGE.start(), *IO_BUFFER_SIZE_RANGE.end())
+	});
+	IO_BUFFER_SIZE.store(size, Ordering::Release);
+}
+
+pub fn io_buffer_size() -> usize {
+	IO_BUFFER_SIZE.load(Ordering::Acquire)
+}
+
+pub trait ReadSeek: Read + Seek + Send {}
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Opens a fresh reader over the GMA, e.g. one backed by HTTP range requests.
+/// This is called whenever a new handle is needed, as extraction reads entries from multiple threads.
+pub type GMAReaderSource = Arc<dyn Fn() -> Result<Box<dyn ReadSeek>, std::io::Error> + Send + Sync>;
+
 pub enum GMAReader {
 	MemBuffer(Cursor<ArcBytes>),
 	Disk(BufReader<File>),
+	Custom(BufReader<Box<dyn ReadSeek>>),
 }
 impl std::ops::Deref for GMAReader {
 	type Target = dyn NTStringReader;
@@ -27,6 +62,7 @@ impl std::ops::Deref for GMAReader {
 		match self {
 			Self::MemBuffer(buf) => buf,
 			Self::Disk(buf) => buf,
+			Self::Custom(buf) => buf,
 		}
 	}
 }
@@ -35,21 +71,92 @@ impl std::ops::DerefMut for GMAReader {
 		match self {
 			Self::MemBuffer(buf) => buf,
 			Self::Disk(buf) => buf,
+			Self::Custom(buf) => buf,
 		}
 	}
 }
 impl NTStringReader for Cursor<ArcBytes> {}
 impl NTStringReader for BufReader<File> {}
+impl NTStringReader for BufReader<Box<dyn ReadSeek>> {}
+
+/// One of several readers sharing a single open file, each with its own position, so threads don't move each other's cursors
+struct SharedFileReader {
+	file: Arc<Mutex<File>>,
+	pos: u64,
+}
+impl Read for SharedFileReader {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		let mut file = self.file.lock();
+		file.seek(SeekFrom::Start(self.pos))?;
+		let read = file.read(buf)?;
+		self.pos += read as u64;
+		Ok(read)
+	}
+}
+impl Seek for SharedFileReader {
+	fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+		let mut file = self.file.lock();
+		file.seek(SeekFrom::Start(self.pos))?;
+		self.pos = file.seek(pos)?;
+		Ok(self.pos)
+	}
+}
+
+/// Reads from `file` for as long as the GMA is around, even if its path is moved or deleted in the meantime
+pub(super) fn file_reader_source(file: File) -> GMAReaderSource {
+	let file = Arc::new(Mutex::new(file));
+	Arc::new(move || Ok(Box::new(SharedFileReader { file: file.clone(), pos: 0 }) as Box<dyn ReadSeek>))
+}
 
 impl GMAFile {
+	pub fn open_with_source<P: AsRef<Path>>(path: P, source: GMAReaderSource) -> Result<GMAFile, GMAError> {
+		let mut gma = GMAFile::read_header(GMAReader::Custom(BufReader::new(source()?)), path)?;
+		gma.reader_source = Some(source);
+		Ok(gma)
+	}
+
 	pub fn read(&self) -> Result<GMAReader, GMAError> {
+		self.read_with_capacity(io_buffer_size())
+	}
+
+	/// Like `read`, but with a custom buffer size for the underlying reader. Ignored for in-memory GMAs.
+	pub fn read_with_capacity(&self, capacity: usize) -> Result<GMAReader, GMAError> {
 		if let Some(ref membuffer) = self.membuffer {
 			Ok(GMAReader::MemBuffer(Cursor::new(membuffer.clone())))
+		} else if let Some(ref source) = self.reader_source {
+			Ok(GMAReader::Custom(BufReader::with_capacity(capacity, source()?)))
 		} else {
-			Ok(GMAReader::Disk(BufReader::new(File::open(&self.path)?)))
+			Ok(GMAReader::Disk(BufReader::with_capacity(capacity, File::open(&self.path)?)))
 		}
 	}
 
+	/// Reads the header and just the title, without parsing the rest of the metadata
+	pub fn quick_title<P: AsRef<Path>>(path: P) -> Result<String, GMAError> {
+		main_thread_forbidden!();
+
+		let mut handle = BufReader::new(File::open(path.as_ref())?);
+
+		let mut header_buf = [0; 4];
+		if handle.read_exact(&mut header_buf).is_err() || &header_buf != super::GMA_HEADER {
+			return Err(GMAError::InvalidHeader);
+		}
+
+		let version = handle.read_u8()?;
+		if !super::SUPPORTED_GMA_VERSIONS.contains(&version) {
+			return Err(GMAError::UnsupportedVersion(version));
+		}
+
+		safe_read!(handle.read_u64::<LittleEndian>())?; // steamid [unused]
+		safe_read!(handle.read_u64::<LittleEndian>())?; // timestamp
+
+		if version > 1 {
+			// required content, terminated by an empty string
+			while !safe_read!(handle.read_nt_string())?.is_empty() {}
+		}
+
+		safe_read!(handle.read_nt_string())
+	}
+
 	pub fn metadata(&mut self) -> Result<Option<GMAReader>, GMAError> {
 		main_thread_forbidden!();
 
@@ -59,19 +166,34 @@ impl GMAFile {
 			let mut handle = self.read()?;
 			handle.seek(SeekFrom::Start(self.pointers.metadata))?;
 
-			safe_read!(handle.read_u64::<LittleEndian>())?; // steamid [unused]
-			safe_read!(handle.read_u64::<LittleEndian>())?; // timestamp
+			self.author = Some(safe_read!(handle.read_u64::<LittleEndian>())?);
+			self.timestamp = Some(safe_read!(handle.read_u64::<LittleEndian>())?);
 
 			if self.version > 1 {
-				// required content [unused]
-				safe_read!(handle.skip_nt_string())?;
+				// required content, terminated by an empty string
+				let mut required_content = Vec::new();
+				loop {
+					let content = safe_read!(handle.read_nt_string())?;
+					if content.is_empty() {
+						break;
+					}
+					required_content.push(content);
+				}
+				self.required_content = required_content;
 			}
 
 			let embedded_title = safe_read!(handle.read_nt_string())?;
 			let embedded_description = safe_read!(handle.read_nt_string())?;
 
-			self.metadata = Some(match serde_json::de::from_str::<GMAMetadata>(&embedded_description) {
-				Ok(mut metadata) => {
+			self.metadata = Some(match crate::from_str_lenient::<GMAMetadata>(&embedded_description) {
+				Ok((mut metadata, lenient)) => {
+					if lenient {
+						log::warn!(
+							"The addon.json in {} has comments or trailing commas, which aren't valid JSON. Repack it with a fixed addon.json",
+							self.path.display()
+						);
+					}
+
 					match &mut metadata {
 						GMAMetadata::Standard { title, .. } => *title = embedded_title,
 						GMAMetadata::Legacy { title, description } => {
@@ -100,11 +222,13 @@ impl GMAFile {
 
 	// https://steamcommunity.com/sharedfiles/filedetails/?id=1727993520
 
-	pub fn entries(&mut self) -> Result<Option<GMAReader>, GMAError> {
+	/// Loads the entries table into `self.entries`. Nothing past the end of the table is read, short of what the
+	/// `io_buffer_size` buffer prefetches. The file handle is closed before this returns
+	pub fn entries(&mut self) -> Result<(), GMAError> {
 		main_thread_forbidden!();
 
 		if self.entries.is_some() {
-			Ok(None)
+			Ok(())
 		} else {
 			let mut handle = match self.metadata()? {
 				Some(handle) => handle,
@@ -114,11 +238,30 @@ impl GMAFile {
 
 			let mut entries = HashMap::new();
 			let mut entry_cursor = 0;
+			let mut entry_count: usize = 0;
 
 			'read_entries: while handle.read_u32::<LittleEndian>()? != 0 {
+				entry_count += 1;
+				if entry_count > self.max_entries {
+					log::warn!("GMA has more than {} entries: {}", self.max_entries, self.path.display());
+					return Err(GMAError::FormatError);
+				}
+
 				let path = handle.read_nt_string()?;
 				let size = handle.read_i64::<LittleEndian>()? as u64;
 				let crc = handle.read_u32::<LittleEndian>()?;
+				let flags = if self.version >= super::GMA_VERSION_ENTRY_FLAGS {
+					handle.read_u8()?
+				} else {
+					0
+				};
+
+				// The entry's contents still take up space in the data section, even if it gets skipped below
+				let index = entry_cursor;
+				entry_cursor = match entry_cursor.checked_add(size) {
+					None => return Err(GMAError::FormatError),
+					Some(entry_cursor) => entry_cursor,
+				};
 
 				// Detect ../ and skip this entry if found to prevent directory traversal attack
 				{
@@ -138,7 +281,7 @@ impl GMAFile {
 							}
 							FORWARDS_SLASH | BACKWARDS_SLASH => {
 								if dots == 2 {
-									eprintln!("Illegal GMA entry: {}", path);
+									log::warn!("Illegal GMA entry: {}", path);
 									continue 'read_entries;
 								} else {
 									dots = 0;
@@ -153,21 +296,419 @@ impl GMAFile {
 					path: path.clone(),
 					size,
 					crc,
-					index: entry_cursor,
-				};
-
-				entry_cursor = match entry_cursor.checked_add(size) {
-					None => return Err(GMAError::FormatError),
-					Some(entry_cursor) => entry_cursor,
+					index,
+					compressed: flags & super::GMA_ENTRY_FLAG_COMPRESSED != 0,
 				};
 
 				entries.insert(path, entry);
 			}
 
+			log::debug!("Loaded {} entries from {}", entries.len(), self.path.display());
 			self.entries = Some(entries);
 			self.pointers.entries = handle.seek(SeekFrom::Current(0))?;
 
-			Ok(Some(handle))
+			Ok(())
+		}
+	}
+
+	/// Like `entries()`, but allowed on the main thread for GMAs up to `BLOCKING_ENTRIES_MAX_SIZE`, saving the UI a thread hop for small addons.
+	/// Anything larger fails with `GMAError::TooLargeForBlocking` and should go through `entries()` on a background thread
+	pub fn try_entries_blocking(&mut self) -> Result<(), GMAError> {
+		if self.entries.is_some() {
+			return Ok(());
+		}
+
+		if self.size > super::BLOCKING_ENTRIES_MAX_SIZE {
+			return Err(GMAError::TooLargeForBlocking(self.size));
+		}
+
+		crate::allow_main_thread(|| self.entries())
+	}
+
+	/// How many bytes the entries table takes up, including its terminator. Reads the table if it hasn't been already
+	pub fn entries_table_size(&mut self) -> Result<u64, GMAError> {
+		self.entries()?;
+		Ok(self.pointers.entries - self.pointers.entries_list)
+	}
+
+	/// Entries sorted by path, including their sizes and CRCs, for the frontend's file tree
+	pub fn entries_detailed(&self) -> Option<Vec<GMAEntry>> {
+		let mut entries: Vec<GMAEntry> = self.entries.as_ref()?.values().cloned().collect();
+		entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+		Some(entries)
+	}
+
+	/// Reads at most `max_bytes` from the start of an entry, for sniffing headers without extracting the whole thing
+	pub fn read_entry_prefix(&mut self, entry_path: &str, max_bytes: usize) -> Result<Vec<u8>, GMAError> {
+		self.entries()?;
+
+		let entry = self.entries.as_ref().unwrap().get(entry_path).ok_or(GMAError::EntryNotFound)?;
+
+		let mut handle = self.read_with_capacity(usize::min(max_bytes, io_buffer_size()))?;
+		handle.seek(SeekFrom::Start(self.pointers.entries + entry.index))?;
+
+		let mut buf = Vec::with_capacity(usize::min(max_bytes, entry.size as usize));
+		if entry.compressed {
+			let stream = xz2::stream::Stream::new_auto_decoder(super::DEFAULT_LZMA_MEMLIMIT, 0).map_err(|_| GMAError::LZMA)?;
+			xz2::bufread::XzDecoder::new_stream((&mut *handle).take(entry.size), stream)
+				.take(max_bytes as u64)
+				.read_to_end(&mut buf)?;
+		} else {
+			(&mut *handle).take(u64::min(max_bytes as u64, entry.size)).read_to_end(&mut buf)?;
 		}
+
+		Ok(buf)
 	}
 }
+
+#[cfg(test)]
+pub(crate) fn test_gma(title: &str, description: &str, entries: &[(&str, &[u8])]) -> Vec<u8> {
+	use crate::NTStringWriter;
+	use byteorder::WriteBytesExt;
+
+	let mut buf = Vec::new();
+	buf.extend_from_slice(super::GMA_HEADER);
+	buf.write_u8(3).unwrap();
+	buf.write_u64::<LittleEndian>(0).unwrap();
+	buf.write_u64::<LittleEndian>(0).unwrap();
+	buf.write_u8(0).unwrap();
+	buf.write_nt_string(title).unwrap();
+	buf.write_nt_string(description).unwrap();
+	buf.write_nt_string("Author Name").unwrap();
+	buf.write_i32::<LittleEndian>(1).unwrap();
+
+	for (i, (path, contents)) in entries.iter().enumerate() {
+		buf.write_u32::<LittleEndian>(i as u32 + 1).unwrap();
+		buf.write_nt_string(path).unwrap();
+		buf.write_i64::<LittleEndian>(contents.len() as i64).unwrap();
+		buf.write_u32::<LittleEndian>(crc32fast::hash(contents)).unwrap();
+	}
+	buf.write_u32::<LittleEndian>(0).unwrap();
+
+	for (_, contents) in entries {
+		buf.extend_from_slice(contents);
+	}
+	buf.write_u32::<LittleEndian>(0).unwrap();
+
+	buf
+}
+
+#[test]
+fn test_open_skips_entries() {
+	// Strip the entries table terminator and the trailing CRC, so any attempt to read the entries table fails
+	let mut gma = test_gma("Test Addon", r#"{"type":"tool","tags":["fun"]}"#, &[]);
+	gma.truncate(gma.len() - 8);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_open_skips_entries.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	let opened = GMAFile::open(&path).unwrap();
+	assert!(opened.metadata.is_none());
+	assert!(opened.entries.is_none());
+
+	let mut opened = GMAFile::open_metadata_only(&path).unwrap();
+	assert_eq!(opened.metadata.as_ref().map(|metadata| metadata.title()), Some("Test Addon"));
+	assert!(opened.entries.is_none());
+	assert_eq!(opened.pointers.entries_list, gma.len() as u64);
+	assert!(opened.entries().is_err());
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_metadata_extra_fields() {
+	let gma = test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":["fun"],"logo":"logo.png","menu_icon":{"size":16}}"#,
+		&[],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_metadata_extra_fields.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	let gma = GMAFile::open_metadata_only(&path).unwrap();
+	let json = serde_json::to_value(gma.metadata.as_ref().unwrap()).unwrap();
+	assert_eq!(json["type"], "tool");
+	assert_eq!(json["logo"], "logo.png");
+	assert_eq!(json["menu_icon"]["size"], 16);
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_metadata_description() {
+	let gma = test_gma("Test Addon", r#"{"type":"tool","tags":[],"description":"Does a thing"}"#, &[]);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_metadata_description.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	let gma = GMAFile::open_metadata_only(&path).unwrap();
+	let metadata = gma.metadata.as_ref().unwrap();
+	assert!(matches!(metadata, GMAMetadata::Standard { .. }));
+	assert_eq!(metadata.description(), "Does a thing");
+
+	let json = serde_json::to_value(metadata).unwrap();
+	assert_eq!(json["description"], "Does a thing");
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_entries_closes_file() {
+	let path = std::env::temp_dir().join("gmpublisher_test_entries_closes_file.gma");
+	std::fs::write(
+		&path,
+		test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.entries().unwrap();
+
+	// Fails on Windows if the handle is still open
+	std::fs::remove_file(&path).unwrap();
+
+	assert!(gma.entries.as_ref().unwrap().contains_key("lua/autorun/a.lua"));
+}
+
+#[test]
+fn test_entries_skips_traversal() {
+	let path = std::env::temp_dir().join("gmpublisher_test_entries_skips_traversal.gma");
+	std::fs::write(
+		&path,
+		test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/../../evil.lua", b"print('evil')"), ("lua/autorun/a.lua", b"print('a')")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.entries().unwrap();
+
+	let entries = gma.entries.as_ref().unwrap();
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries["lua/autorun/a.lua"].index, b"print('evil')".len() as u64);
+	assert_eq!(gma.read_entry_prefix("lua/autorun/a.lua", 64).unwrap(), b"print('a')");
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_quick_title() {
+	let path = std::env::temp_dir().join("gmpublisher_test_quick_title.gma");
+	std::fs::write(&path, test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[])).unwrap();
+
+	assert_eq!(GMAFile::quick_title(&path).unwrap(), "Test Addon");
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_max_entries() {
+	let path = std::env::temp_dir().join("gmpublisher_test_max_entries.gma");
+	std::fs::write(
+		&path,
+		test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"a"), ("lua/autorun/b.lua", b"b"), ("lua/autorun/c.lua", b"c")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.max_entries = 2;
+	assert!(matches!(gma.entries(), Err(GMAError::FormatError)));
+	assert!(gma.entries.is_none());
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.max_entries = 3;
+	gma.entries().unwrap();
+	assert_eq!(gma.entries.as_ref().unwrap().len(), 3);
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_open_file() {
+	let path = std::env::temp_dir().join("gmpublisher_test_open_file.gma");
+	let gma = test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[]);
+	std::fs::write(&path, &gma).unwrap();
+
+	let opened = GMAFile::open_file(File::open(&path).unwrap(), path.clone()).unwrap();
+	assert_eq!(opened.version, 3);
+	assert_eq!(opened.size, gma.len() as u64);
+	assert_eq!(opened.path, path);
+
+	std::fs::remove_file(path).ok();
+}
+
+// Windows won't delete a file that's still open
+#[cfg(unix)]
+#[test]
+fn test_open_file_outlives_path() {
+	let path = std::env::temp_dir().join("gmpublisher_test_open_file_outlives_path.gma");
+	std::fs::write(
+		&path,
+		test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open_file(File::open(&path).unwrap(), path.clone()).unwrap();
+	std::fs::remove_file(&path).unwrap();
+
+	gma.metadata().unwrap();
+	assert_eq!(gma.metadata.as_ref().map(|metadata| metadata.title()), Some("Test Addon"));
+
+	gma.entries().unwrap();
+	assert!(gma.entries.as_ref().unwrap().contains_key("lua/autorun/a.lua"));
+	assert_eq!(gma.read_entry_prefix("lua/autorun/a.lua", 64).unwrap(), b"print('a')");
+}
+
+#[test]
+fn test_unsupported_version() {
+	let mut gma = test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[]);
+	gma[super::GMA_HEADER.len()] = 5;
+
+	let path = std::env::temp_dir().join("gmpublisher_test_unsupported_version.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	assert!(matches!(GMAFile::open(&path), Err(GMAError::UnsupportedVersion(5))));
+	assert!(matches!(GMAFile::quick_title(&path), Err(GMAError::UnsupportedVersion(5))));
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_io_buffer_size() {
+	set_io_buffer_size(Some(1));
+	assert_eq!(io_buffer_size(), *IO_BUFFER_SIZE_RANGE.start());
+
+	set_io_buffer_size(Some(usize::MAX));
+	assert_eq!(io_buffer_size(), *IO_BUFFER_SIZE_RANGE.end());
+
+	set_io_buffer_size(None);
+	assert_eq!(io_buffer_size(), DEFAULT_BUF_SIZE);
+}
+
+#[test]
+fn test_required_content() {
+	let mut gma = test_gma("Test Addon", r#"{"type":"map","tags":[]}"#, &[("maps/test.bsp", b"VBSP")]);
+
+	// test_gma writes an empty list right after the header, steamid and timestamp
+	gma.splice(21..21, b"cstrike\0episodic\0".iter().copied());
+
+	let path = std::env::temp_dir().join("gmpublisher_test_required_content.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	assert_eq!(GMAFile::quick_title(&path).unwrap(), "Test Addon");
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.entries().unwrap();
+	assert_eq!(gma.required_content, ["cstrike", "episodic"]);
+	assert_eq!(gma.metadata.as_ref().unwrap().title(), "Test Addon");
+	assert!(gma.entries.as_ref().unwrap().contains_key("maps/test.bsp"));
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_read_entry_prefix() {
+	let gma = test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":[]}"#,
+		&[("lua/autorun/a.lua", b"print('a')"), ("materials/b.vtf", b"VTF\0rest of the texture")],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_read_entry_prefix.gma");
+	std::fs::write(&path, gma).unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert_eq!(gma.read_entry_prefix("materials/b.vtf", 4).unwrap(), b"VTF\0");
+	assert_eq!(gma.read_entry_prefix("lua/autorun/a.lua", 1024).unwrap(), b"print('a')");
+	assert_eq!(gma.read_entry_prefix("lua/autorun/a.lua", 0).unwrap(), b"");
+	assert!(matches!(gma.read_entry_prefix("missing.lua", 4), Err(GMAError::EntryNotFound)));
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_entries_table_size() {
+	let gma = test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":[]}"#,
+		&[("lua/autorun/a.lua", b"print('a')"), ("materials/b.vmt", b"\"VertexLitGeneric\" {}")],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_entries_table_size.gma");
+	std::fs::write(&path, gma).unwrap();
+
+	// Each entry is its number, path, size and CRC, and the table ends with a 0 entry number
+	let expected = (4 + "lua/autorun/a.lua".len() + 1 + 8 + 4) + (4 + "materials/b.vmt".len() + 1 + 8 + 4) + 4;
+	assert_eq!(GMAFile::open(&path).unwrap().entries_table_size().unwrap(), expected as u64);
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_open_lenient() {
+	let gma = test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]);
+
+	let mut prefixed = vec![0xEF, 0xBB, 0xBF];
+	prefixed.extend_from_slice(&gma);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_open_lenient.gma");
+	std::fs::write(&path, prefixed).unwrap();
+
+	assert!(matches!(GMAFile::open(&path), Err(GMAError::InvalidHeader)));
+
+	let mut opened = GMAFile::open_lenient(&path).unwrap();
+	assert_eq!(opened.pointers.header(), 3);
+	assert_eq!(opened.read_entry_prefix("lua/autorun/a.lua", 64).unwrap(), b"print('a')");
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_try_entries_blocking() {
+	let path = std::env::temp_dir().join("gmpublisher_test_try_entries_blocking.gma");
+	std::fs::write(
+		&path,
+		test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+
+	// Pretend it's too big to be read on the main thread
+	gma.size = super::BLOCKING_ENTRIES_MAX_SIZE + 1;
+	assert!(matches!(gma.try_entries_blocking(), Err(GMAError::TooLargeForBlocking(_))));
+
+	gma.size = 1024;
+	gma.try_entries_blocking().unwrap();
+	assert!(gma.entries.as_ref().unwrap().contains_key("lua/autorun/a.lua"));
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_metadata_lenient_json() {
+	let path = std::env::temp_dir().join("gmpublisher_test_metadata_lenient_json.gma");
+	std::fs::write(
+		&path,
+		test_gma(
+			"Test Addon",
+			"{\n\t// hand-edited\n\t\"type\": \"tool\",\n\t\"tags\": [\"fun\",],\n}",
+			&[("lua/autorun/a.lua", b"print('a')")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.metadata().unwrap();
+	assert!(matches!(gma.metadata, Some(GMAMetadata::Standard { ref addon_type, .. }) if addon_type == "tool"));
+
+	std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use steamworks::PublishedFileId;
+
+use super::{AddonTag, AddonType, GMAError, GMAFile, GMAMetadata};
+
+/// The parts of a Workshop item's details we can use, in the shape the Steam Web API's `GetPublishedFileDetails` returns them.
+/// Download tools often save this next to the downloaded GMA
+#[derive(Deserialize)]
+struct WorkshopSidecar {
+	#[serde(default)]
+	publishedfileid: Option<SidecarId>,
+	#[serde(default)]
+	title: Option<String>,
+	#[serde(default)]
+	tags: Vec<SidecarTag>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SidecarId {
+	Number(u64),
+	String(String),
+}
+impl SidecarId {
+	fn get(&self) -> Option<PublishedFileId> {
+		match self {
+			SidecarId::Number(id) => Some(*id),
+			SidecarId::String(id) => id.parse().ok(),
+		}
+		.filter(|id| *id != 0)
+		.map(PublishedFileId)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SidecarTag {
+	Plain(String),
+	Object { tag: String },
+}
+impl SidecarTag {
+	fn as_str(&self) -> &str {
+		match self {
+			SidecarTag::Plain(tag) | SidecarTag::Object { tag } => tag,
+		}
+	}
+}
+
+/// Reads a Workshop details file, ignoring anything without a Workshop ID, such as the addon.json an addon was packed from
+fn read_workshop_sidecar(path: &Path) -> Option<WorkshopSidecar> {
+	match std::fs::read_to_string(path).map(|json| serde_json::from_str::<WorkshopSidecar>(&json)) {
+		Ok(Ok(sidecar)) if sidecar.publishedfileid.as_ref().and_then(SidecarId::get).is_some() => Some(sidecar),
+		Ok(Ok(_)) => None,
+		Ok(Err(err)) => {
+			log::warn!("Malformed Workshop sidecar {}: {}", path.display(), err);
+			None
+		}
+		Err(err) => {
+			log::warn!("Failed to read Workshop sidecar {}: {}", path.display(), err);
+			None
+		}
+	}
+}
+
+impl GMAFile {
+	/// `addon.gma` -> `addon.json`, then `workshop.json` in the same folder
+	fn workshop_sidecar_paths(&self) -> Vec<PathBuf> {
+		let mut paths = Vec::with_capacity(2);
+		if let Some(file_stem) = self.path.file_stem() {
+			let mut file_name = file_stem.to_owned();
+			file_name.push(".json");
+			paths.push(self.path.with_file_name(file_name));
+		}
+		paths.push(self.path.with_file_name("workshop.json"));
+		paths
+	}
+
+	/// Looks for a Workshop details file next to the GMA and uses it to fill in whatever the GMA itself is missing:
+	/// the Workshop ID, an empty title, the addon type and any tags the Workshop knows about.
+	/// Nothing the GMA already has is overwritten. A sidecar only counts if it has a Workshop ID. Returns whether one was found
+	pub fn enrich_from_workshop_sidecar(&mut self) -> Result<bool, GMAError> {
+		main_thread_forbidden!();
+
+		let sidecar = match self
+			.workshop_sidecar_paths()
+			.into_iter()
+			.filter(|path| path.is_file())
+			.find_map(|path| read_workshop_sidecar(&path))
+		{
+			Some(sidecar) => sidecar,
+			None => return Ok(false),
+		};
+
+		self.metadata()?;
+
+		if let Some(metadata) = &mut self.metadata {
+			if let Some(sidecar_title) = sidecar.title.filter(|title| !title.trim().is_empty()) {
+				match metadata {
+					GMAMetadata::Standard { title, .. } | GMAMetadata::Legacy { title, .. } => {
+						if title.trim().is_empty() {
+							*title = sidecar_title;
+						}
+					}
+				}
+			}
+
+			if let GMAMetadata::Standard { addon_type, tags, .. } = metadata {
+				for tag in sidecar.tags.iter().map(SidecarTag::as_str) {
+					if addon_type.is_empty() {
+						if let Ok(sidecar_type) = tag.parse::<AddonType>() {
+							if !matches!(sidecar_type, AddonType::Unknown(_)) {
+								*addon_type = sidecar_type.to_string();
+								continue;
+							}
+						}
+					}
+
+					if let Ok(sidecar_tag) = tag.parse::<AddonTag>() {
+						if !matches!(sidecar_tag, AddonTag::Unknown(_)) {
+							let sidecar_tag = sidecar_tag.to_string();
+							if !tags.contains(&sidecar_tag) {
+								tags.push(sidecar_tag);
+							}
+						}
+					}
+				}
+			}
+		}
+
+		if self.id.is_none() {
+			self.id = sidecar.publishedfileid.as_ref().and_then(SidecarId::get);
+		}
+
+		self.compute_extracted_name();
+
+		Ok(true)
+	}
+}
+
+#[test]
+fn test_enrich_from_workshop_sidecar() {
+	let dir = std::env::temp_dir().join("gmpublisher_test_enrich_from_workshop_sidecar");
+	std::fs::remove_dir_all(&dir).ok();
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let path = dir.join("addon.gma");
+	std::fs::write(&path, super::read::test_gma("", r#"{"type":"","tags":["fun"]}"#, &[])).unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert!(!gma.enrich_from_workshop_sidecar().unwrap());
+
+	// The addon.json the addon was authored with isn't a sidecar
+	std::fs::write(dir.join("addon.json"), r#"{"title":"Authored Title","type":"tool","tags":["roleplay"]}"#).unwrap();
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert!(!gma.enrich_from_workshop_sidecar().unwrap());
+	assert_eq!(gma.id, None);
+
+	std::fs::write(
+		dir.join("addon.json"),
+		r#"{"publishedfileid":"123456","title":"Workshop Title","tags":[{"tag":"Addon"},{"tag":"Weapon"},{"tag":"Fun"},{"tag":"Build"}]}"#,
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert!(gma.enrich_from_workshop_sidecar().unwrap());
+	assert_eq!(gma.id, Some(PublishedFileId(123456)));
+
+	let metadata = gma.metadata.as_ref().unwrap();
+	assert_eq!(metadata.title(), "Workshop Title");
+	assert_eq!(metadata.addon_type(), Some("weapon"));
+	assert_eq!(metadata.tags().unwrap(), &vec!["fun".to_string(), "build".to_string()]);
+	assert_eq!(gma.extracted_name, "workshop_title_123456");
+
+	std::fs::remove_dir_all(dir).ok();
+}
@@ -0,0 +1,217 @@
+use std::{
+	collections::{HashMap, HashSet},
+	convert::Infallible,
+	fmt::Display,
+	str::FromStr,
+};
+
+use super::GMAFile;
+
+macro_rules! string_enum {
+	($(#[$meta:meta])* $name:ident { $($variant:ident => $str:literal),+ $(,)? }) => {
+		$(#[$meta])*
+		#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+		pub enum $name {
+			$($variant,)+
+			Unknown(String),
+		}
+		impl FromStr for $name {
+			type Err = Infallible;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				$(if s.eq_ignore_ascii_case($str) {
+					return Ok(Self::$variant);
+				})+
+				Ok(Self::Unknown(s.to_owned()))
+			}
+		}
+		impl Display for $name {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				match self {
+					$(Self::$variant => f.write_str($str),)+
+					Self::Unknown(unknown) => f.write_str(unknown),
+				}
+			}
+		}
+	};
+}
+
+string_enum!(
+	/// The `type` of an addon.json, as accepted by the Workshop
+	AddonType {
+		ServerContent => "ServerContent",
+		Gamemode => "gamemode",
+		Map => "map",
+		Weapon => "weapon",
+		Vehicle => "vehicle",
+		Npc => "npc",
+		Tool => "tool",
+		Effects => "effects",
+		Model => "model",
+		Entity => "entity",
+	}
+);
+
+string_enum!(
+	/// One of the `tags` of an addon.json, as accepted by the Workshop
+	AddonTag {
+		Fun => "fun",
+		Roleplay => "roleplay",
+		Scenic => "scenic",
+		Movie => "movie",
+		Realism => "realism",
+		Cartoon => "cartoon",
+		Water => "water",
+		Comic => "comic",
+		Build => "build",
+	}
+);
+
+impl GMAFile {
+	/// `None` if the metadata hasn't been read or is legacy
+	pub fn addon_type_enum(&self) -> Option<AddonType> {
+		let addon_type = self.metadata.as_ref()?.addon_type()?;
+		Some(addon_type.parse().unwrap())
+	}
+
+	/// `None` if the metadata hasn't been read or is legacy
+	pub fn tags_enum(&self) -> Option<Vec<AddonTag>> {
+		let tags = self.metadata.as_ref()?.tags()?;
+		Some(tags.iter().map(|tag| tag.parse().unwrap()).collect())
+	}
+
+	/// Workshop tags the entries hint at, in their Workshop spelling, for comparing with the declared `tags`.
+	/// Only guesses from paths, so it's a nudge rather than an answer. Empty if the entries can't be read
+	pub fn suggest_tags(&mut self) -> Vec<String> {
+		if self.entries().is_err() {
+			return Vec::new();
+		}
+
+		let mut suggested = HashSet::new();
+		let mut suggest = |tag: AddonTag| {
+			suggested.insert(tag);
+		};
+
+		for path in self.entries.as_ref().unwrap().keys() {
+			if let Some(map) = path.strip_prefix("maps/").and_then(|map| map.strip_suffix(".bsp")) {
+				match map.split_once('_').map(|(prefix, _)| prefix) {
+					Some("rp") => suggest(AddonTag::Roleplay),
+					Some("gm") | Some("sb") => suggest(AddonTag::Build),
+					Some("ttt") | Some("dm") | Some("zs") | Some("mg") | Some("jb") | Some("surf") | Some("bhop") | Some("deathrun") => {
+						suggest(AddonTag::Fun)
+					}
+					_ => suggest(AddonTag::Scenic),
+				}
+			} else if let Some(gamemode) = path.strip_prefix("gamemodes/").and_then(|path| path.split('/').next()) {
+				if gamemode.contains("rp") {
+					suggest(AddonTag::Roleplay);
+				} else {
+					suggest(AddonTag::Fun);
+				}
+			} else if path.starts_with("lua/weapons/gmod_tool/stools/") {
+				suggest(AddonTag::Build);
+			} else if path.starts_with("materials/") && path.contains("water") {
+				suggest(AddonTag::Water);
+			} else if path.starts_with("lua/pac3/") || path.starts_with("lua/ssm/") {
+				suggest(AddonTag::Movie);
+			}
+		}
+
+		// Entries come out of a HashMap, so order them the way the Workshop lists them
+		[
+			AddonTag::Fun,
+			AddonTag::Roleplay,
+			AddonTag::Scenic,
+			AddonTag::Movie,
+			AddonTag::Realism,
+			AddonTag::Cartoon,
+			AddonTag::Water,
+			AddonTag::Comic,
+			AddonTag::Build,
+		]
+		.into_iter()
+		.filter(|tag| suggested.contains(tag))
+		.map(|tag| tag.to_string())
+		.collect()
+	}
+}
+
+/// How many of the GMAs there are of each addon type, keyed by the type's Workshop spelling regardless of how each addon.json cased it.
+/// GMAs without a type (legacy, or metadata not read) aren't counted
+pub fn summarize_library<'a, I: IntoIterator<Item = &'a GMAFile>>(gmas: I) -> HashMap<String, usize> {
+	let mut summary = HashMap::new();
+	for addon_type in gmas.into_iter().filter_map(GMAFile::addon_type_enum) {
+		*summary.entry(addon_type.to_string()).or_insert(0) += 1;
+	}
+	summary
+}
+
+#[test]
+fn test_addon_tags() {
+	assert_eq!("ServerContent".parse::<AddonType>().unwrap(), AddonType::ServerContent);
+	assert_eq!("Weapon".parse::<AddonType>().unwrap(), AddonType::Weapon);
+	assert_eq!("servercontent".parse::<AddonType>().unwrap().to_string(), "ServerContent");
+	assert_eq!("fun".parse::<AddonTag>().unwrap(), AddonTag::Fun);
+	assert_eq!("shiny".parse::<AddonTag>().unwrap(), AddonTag::Unknown("shiny".to_string()));
+	assert_eq!(AddonTag::Unknown("shiny".to_string()).to_string(), "shiny");
+}
+
+#[test]
+fn test_summarize_library() {
+	use super::GMAMetadata;
+
+	let gma = |addon_type: &str| {
+		GMAFile::new(
+			"test.gma",
+			GMAMetadata::Standard {
+				title: "Test Addon".to_string(),
+				addon_type: addon_type.to_string(),
+				description: String::new(),
+				tags: vec![],
+				ignore: vec![],
+				extra: Default::default(),
+			},
+		)
+	};
+
+	let gmas = [
+		gma("map"),
+		gma("Map"),
+		gma("gamemode"),
+		GMAFile::new(
+			"legacy.gma",
+			GMAMetadata::Legacy {
+				title: "Legacy Addon".to_string(),
+				description: String::new(),
+			},
+		),
+	];
+
+	let summary = summarize_library(&gmas);
+	assert_eq!(summary.len(), 2);
+	assert_eq!(summary["map"], 2);
+	assert_eq!(summary["gamemode"], 1);
+}
+
+#[test]
+fn test_suggest_tags() {
+	let path = std::env::temp_dir().join("gmpublisher_test_suggest_tags.gma");
+	std::fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"map","tags":[]}"#,
+			&[
+				("maps/rp_downtown.bsp", b"VBSP"),
+				("maps/gm_flatgrass_2.bsp", b"VBSP"),
+				("materials/nature/water_lake.vmt", b""),
+				("lua/autorun/a.lua", b"print('a')"),
+			],
+		),
+	)
+	.unwrap();
+
+	assert_eq!(GMAFile::open(&path).unwrap().suggest_tags(), ["roleplay", "water", "build"]);
+
+	std::fs::remove_file(path).ok();
+}
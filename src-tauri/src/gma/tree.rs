@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{GMAEntry, GMAError, GMAFile};
+
+/// A GMA's entries as a nested directory tree. Directories are listed before files, both sorted by name
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TreeNode {
+	Directory {
+		name: String,
+		path: String,
+		/// The total size of every file under this directory
+		size: u64,
+		children: Vec<TreeNode>,
+	},
+	File {
+		name: String,
+		path: String,
+		size: u64,
+	},
+}
+impl TreeNode {
+	pub fn name(&self) -> &str {
+		match self {
+			TreeNode::Directory { name, .. } | TreeNode::File { name, .. } => name,
+		}
+	}
+
+	pub fn size(&self) -> u64 {
+		match self {
+			TreeNode::Directory { size, .. } | TreeNode::File { size, .. } => *size,
+		}
+	}
+}
+
+#[derive(Default)]
+struct DirectoryBuilder<'a> {
+	dirs: BTreeMap<&'a str, DirectoryBuilder<'a>>,
+	files: BTreeMap<&'a str, (&'a str, u64)>,
+}
+impl<'a> DirectoryBuilder<'a> {
+	fn build(self, name: String, path: String) -> TreeNode {
+		let mut children = Vec::with_capacity(self.dirs.len() + self.files.len());
+		let mut size = 0;
+
+		for (dir_name, dir) in self.dirs {
+			let dir_path = if path.is_empty() {
+				dir_name.to_owned()
+			} else {
+				format!("{}/{}", path, dir_name)
+			};
+			let dir = dir.build(dir_name.to_owned(), dir_path);
+			size += dir.size();
+			children.push(dir);
+		}
+
+		for (file_name, (file_path, file_size)) in self.files {
+			size += file_size;
+			children.push(TreeNode::File {
+				name: file_name.to_owned(),
+				path: file_path.to_owned(),
+				size: file_size,
+			});
+		}
+
+		TreeNode::Directory { name, path, size, children }
+	}
+}
+
+impl TreeNode {
+	/// Builds a directory tree out of entries with `/`-separated paths, such as a GMA's or `scan_content`'s.
+	/// The root is an unnamed directory with an empty path
+	pub fn from_entries<'a, I: IntoIterator<Item = &'a GMAEntry>>(entries: I) -> TreeNode {
+		let mut root = DirectoryBuilder::default();
+		for entry in entries {
+			let (dirs, file_name) = match entry.path.rsplit_once('/') {
+				Some((dirs, file_name)) => (Some(dirs), file_name),
+				None => (None, entry.path.as_str()),
+			};
+
+			let mut dir = &mut root;
+			for component in dirs.into_iter().flat_map(|dirs| dirs.split('/')) {
+				dir = dir.dirs.entry(component).or_default();
+			}
+
+			dir.files.insert(file_name, (entry.path.as_str(), entry.size));
+		}
+
+		root.build(String::new(), String::new())
+	}
+}
+
+impl GMAFile {
+	/// The entries as a directory tree. See `TreeNode::from_entries`
+	pub fn entry_tree(&mut self) -> Result<TreeNode, GMAError> {
+		self.entries()?;
+		Ok(TreeNode::from_entries(self.entries.as_ref().unwrap().values()))
+	}
+}
+
+#[test]
+fn test_entry_tree() {
+	let path = std::env::temp_dir().join("gmpublisher_test_entry_tree.gma");
+	std::fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[
+				("lua/autorun/b.lua", b"print('b')"),
+				("lua/autorun/a.lua", b"print('a')"),
+				("lua/c.lua", b"c"),
+				("models/d.mdl", b"dd"),
+			],
+		),
+	)
+	.unwrap();
+
+	let tree = GMAFile::open(&path).unwrap().entry_tree().unwrap();
+	assert_eq!(tree.size(), 10 + 10 + 1 + 2);
+
+	let children = match &tree {
+		TreeNode::Directory { children, .. } => children,
+		TreeNode::File { .. } => panic!("Expected the root to be a directory"),
+	};
+	assert_eq!(children.iter().map(TreeNode::name).collect::<Vec<_>>(), ["lua", "models"]);
+
+	match &children[0] {
+		TreeNode::Directory { path, size, children, .. } => {
+			assert_eq!(path, "lua");
+			assert_eq!(*size, 21);
+			assert_eq!(children.iter().map(TreeNode::name).collect::<Vec<_>>(), ["autorun", "c.lua"]);
+			assert!(matches!(&children[0], TreeNode::Directory { path, children, .. } if path == "lua/autorun" && children.len() == 2));
+			assert_eq!(
+				children[1],
+				TreeNode::File {
+					name: "c.lua".to_string(),
+					path: "lua/c.lua".to_string(),
+					size: 1,
+				}
+			);
+		}
+		TreeNode::File { .. } => panic!("Expected lua/ to be a directory"),
+	}
+
+	let json = serde_json::to_value(&tree).unwrap();
+	assert_eq!(json["type"], "directory");
+	assert_eq!(json["children"][1]["children"][0]["type"], "file");
+
+	std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,183 @@
+use std::{
+	io::{BufWriter, Read, SeekFrom},
+	path::PathBuf,
+};
+
+use serde::Serialize;
+
+use crate::transactions::Transaction;
+
+use super::{extract::SEQUENTIAL_READ_BUFFER_SIZE, GMAError, GMAFile, GmaFormat, DEFAULT_LZMA_MEMLIMIT};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrcMismatch {
+	pub path: String,
+	pub expected: u32,
+	pub actual: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+	pub passed: bool,
+	pub mismatches: Vec<CrcMismatch>,
+	/// Entries with a stored CRC of 0, which some tools write instead of computing one
+	pub unchecked: Vec<String>,
+}
+
+impl GMAFile {
+	/// Checks every entry's stored CRC against its contents, reading the GMA front to back once.
+	/// Uses whichever `EntryHash` the metadata says the GMA was written with
+	pub fn verify(&mut self, transaction: &Transaction) -> Result<VerifyReport, GMAError> {
+		main_thread_forbidden!();
+
+		self.entries()?;
+
+		let entry_hash = self.entry_hash();
+
+		let mut entries: Vec<_> = self.entries.as_ref().unwrap().values().collect();
+		entries.sort_unstable_by_key(|entry| entry.index);
+
+		let entries_len_f = entries.len() as f64;
+
+		let mut handle = self.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+		handle.seek(SeekFrom::Start(self.pointers.entries))?;
+
+		let mut mismatches = Vec::new();
+		let mut unchecked = Vec::new();
+
+		let mut cursor = 0;
+		for (i, entry) in entries.into_iter().enumerate() {
+			if transaction.aborted() {
+				return Err(GMAError::Cancelled);
+			}
+
+			let gap = entry.index - cursor;
+			if gap > 0 {
+				std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+			}
+
+			let mut w = BufWriter::new(entry_hash.hasher());
+			if entry.compressed {
+				GMAFile::decompress_entry_bytes(&mut handle, &mut w, entry)?;
+			} else {
+				crate::stream_bytes(&mut *handle, &mut w, entry.size as usize)?;
+			}
+			let actual = w.into_inner().map_err(|_| GMAError::IOError)?.finalize();
+
+			if entry.crc == 0 {
+				unchecked.push(entry.path.clone());
+			} else if entry.crc != actual {
+				mismatches.push(CrcMismatch {
+					path: entry.path.clone(),
+					expected: entry.crc,
+					actual,
+				});
+			}
+
+			cursor = entry.index + entry.size;
+
+			transaction.progress(((i + 1) as f64) / entries_len_f);
+		}
+
+		mismatches.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+		unchecked.sort_unstable();
+
+		Ok(VerifyReport {
+			passed: mismatches.is_empty(),
+			mismatches,
+			unchecked,
+		})
+	}
+}
+
+#[tauri::command]
+pub fn verify_gma(path: PathBuf) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || {
+		let gma = match GMAFile::peek_format(&path) {
+			Ok(GmaFormat::Uncompressed) => GMAFile::open(&path),
+			Ok(GmaFormat::Lzma) => GMAFile::decompress(&path, DEFAULT_LZMA_MEMLIMIT, transaction.clone()),
+			Ok(GmaFormat::Unknown) => Err(GMAError::InvalidHeader),
+			Err(error) => Err(error),
+		};
+
+		transaction.progress_reset();
+
+		match gma.and_then(|mut gma| gma.verify(&transaction)) {
+			Ok(report) => transaction.finished(report),
+			Err(GMAError::Cancelled) => {}
+			Err(error) => transaction.error(error.to_string(), turbonone!()),
+		}
+	});
+
+	id
+}
+
+#[test]
+fn test_verify() {
+	let mut gma = super::read::test_gma(
+		"Test Addon",
+		r#"{"type":"tool","tags":[]}"#,
+		&[("lua/autorun/a.lua", b"print('a')"), ("lua/autorun/b.lua", b"print('b')")],
+	);
+
+	let path = std::env::temp_dir().join("gmpublisher_test_verify.gma");
+	std::fs::write(&path, &gma).unwrap();
+
+	let report = GMAFile::open(&path).unwrap().verify(&crate::transactions::new()).unwrap();
+	assert!(report.passed);
+	assert!(report.mismatches.is_empty());
+
+	// Corrupt the last byte of b.lua's contents, just before the trailing CRC
+	let corrupt = gma.len() - 5;
+	gma[corrupt] ^= 0xFF;
+	std::fs::write(&path, &gma).unwrap();
+
+	let report = GMAFile::open(&path).unwrap().verify(&crate::transactions::new()).unwrap();
+	assert!(!report.passed);
+	assert_eq!(report.mismatches.len(), 1);
+	assert_eq!(report.mismatches[0].path, "lua/autorun/b.lua");
+	assert_eq!(report.mismatches[0].expected, crc32fast::hash(b"print('b')"));
+
+	std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_verify_xxhash() {
+	use super::{EntryHash, GMAMetadata, WriteOptions};
+
+	let path = std::env::temp_dir().join("gmpublisher_test_verify_xxhash.gma");
+
+	let gma = GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Test Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec![],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	);
+	gma.create_from_memory(
+		vec![("lua/autorun/a.lua".to_string(), b"print('a')".to_vec())],
+		WriteOptions {
+			entry_hash: EntryHash::XxHash32,
+			..Default::default()
+		},
+	)
+	.unwrap();
+
+	let mut written = GMAFile::open(&path).unwrap();
+	written.entries().unwrap();
+	assert_eq!(written.entry_hash(), EntryHash::XxHash32);
+	assert_eq!(
+		written.entries.as_ref().unwrap()["lua/autorun/a.lua"].crc,
+		EntryHash::XxHash32.hash(b"print('a')")
+	);
+	assert!(written.verify(&crate::transactions::new()).unwrap().passed);
+
+	std::fs::remove_file(path).ok();
+}
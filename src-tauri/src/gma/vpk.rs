@@ -0,0 +1,187 @@
+use std::{
+	collections::BTreeMap,
+	io::{Seek, SeekFrom, Write},
+};
+
+/// A minimal writer for version 1 Source engine VPKs.
+///
+/// Everything is stored in the one file, after the directory tree, so there are no numbered `_000.vpk` archives to keep track of.
+/// The tree's size only depends on the paths, so it's written upfront with blank offsets and CRCs and rewritten by `finish`.
+pub struct VpkWriter<W: Write + Seek> {
+	w: W,
+	paths: Vec<String>,
+	/// Offset (from the end of the tree), length and CRC32 of each path, once its contents have been written
+	written: Vec<Option<(u32, u32, u32)>>,
+	data_len: u64,
+}
+
+const VPK_SIGNATURE: u32 = 0x55AA1234;
+const VPK_VERSION: u32 = 1;
+/// The archive index that means the entry's data follows the tree in the same file
+const VPK_EMBEDDED_ARCHIVE: u16 = 0x7FFF;
+const VPK_ENTRY_TERMINATOR: u16 = 0xFFFF;
+/// What the tree stores in place of an empty extension or directory
+const VPK_BLANK: &str = " ";
+
+impl<W: Write + Seek> VpkWriter<W> {
+	/// Reserves the tree for `paths`, which must be unique and relative with forward slashes
+	pub fn new(mut w: W, paths: Vec<String>) -> std::io::Result<Self> {
+		let written = vec![None; paths.len()];
+
+		w.seek(SeekFrom::Start(0))?;
+		write_tree(&mut w, &paths, &written)?;
+
+		Ok(Self {
+			w,
+			paths,
+			written,
+			data_len: 0,
+		})
+	}
+
+	/// Appends the contents of `paths[index]`, which `write` streams into the writer it's given
+	pub fn write_entry<F>(&mut self, index: usize, write: F) -> std::io::Result<()>
+	where
+		F: FnOnce(&mut dyn Write) -> std::io::Result<()>,
+	{
+		let offset = u32::try_from(self.data_len).map_err(|_| too_large())?;
+
+		let mut hashed = HashingWriter {
+			w: &mut self.w,
+			hasher: crc32fast::Hasher::new(),
+			len: 0,
+		};
+		write(&mut hashed)?;
+
+		let HashingWriter { hasher, len, .. } = hashed;
+		self.data_len += len;
+
+		self.written[index] = Some((offset, u32::try_from(len).map_err(|_| too_large())?, hasher.finalize()));
+
+		Ok(())
+	}
+
+	/// Rewrites the tree now that every entry's offset and CRC is known. Entries that were never written are left empty
+	pub fn finish(mut self) -> std::io::Result<W> {
+		self.w.seek(SeekFrom::Start(0))?;
+		write_tree(&mut self.w, &self.paths, &self.written)?;
+		self.w.seek(SeekFrom::End(0))?;
+		self.w.flush()?;
+		Ok(self.w)
+	}
+}
+
+fn too_large() -> std::io::Error {
+	std::io::Error::new(std::io::ErrorKind::InvalidInput, "VPKs can't hold more than 4 GiB")
+}
+
+struct HashingWriter<'a, W: Write> {
+	w: &'a mut W,
+	hasher: crc32fast::Hasher,
+	len: u64,
+}
+impl<W: Write> Write for HashingWriter<'_, W> {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		let written = self.w.write(buf)?;
+		self.hasher.update(&buf[..written]);
+		self.len += written as u64;
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.w.flush()
+	}
+}
+
+/// Splits `lua/autorun/a.lua` into (`lua`, `lua/autorun`, `a`), the way the tree groups paths
+fn split_path(path: &str) -> (&str, &str, &str) {
+	let (dir, file) = match path.rsplit_once('/') {
+		Some((dir, file)) if !dir.is_empty() => (dir, file),
+		_ => (VPK_BLANK, path.trim_start_matches('/')),
+	};
+
+	match file.rsplit_once('.') {
+		Some((name, extension)) if !name.is_empty() && !extension.is_empty() => (extension, dir, name),
+		_ => (VPK_BLANK, dir, file),
+	}
+}
+
+fn write_tree<W: Write>(w: &mut W, paths: &[String], written: &[Option<(u32, u32, u32)>]) -> std::io::Result<()> {
+	let mut tree: BTreeMap<&str, BTreeMap<&str, Vec<(&str, usize)>>> = BTreeMap::new();
+	for (index, path) in paths.iter().enumerate() {
+		let (extension, dir, name) = split_path(path);
+		tree.entry(extension).or_default().entry(dir).or_default().push((name, index));
+	}
+
+	let mut buf = Vec::new();
+	for (extension, dirs) in tree {
+		write_str(&mut buf, extension);
+		for (dir, files) in dirs {
+			write_str(&mut buf, dir);
+			for (name, index) in files {
+				write_str(&mut buf, name);
+
+				let (offset, len, crc) = written[index].unwrap_or_default();
+				buf.extend_from_slice(&crc.to_le_bytes());
+				buf.extend_from_slice(&0u16.to_le_bytes()); // Preload bytes
+				buf.extend_from_slice(&VPK_EMBEDDED_ARCHIVE.to_le_bytes());
+				buf.extend_from_slice(&offset.to_le_bytes());
+				buf.extend_from_slice(&len.to_le_bytes());
+				buf.extend_from_slice(&VPK_ENTRY_TERMINATOR.to_le_bytes());
+			}
+			buf.push(0);
+		}
+		buf.push(0);
+	}
+	buf.push(0);
+
+	w.write_all(&VPK_SIGNATURE.to_le_bytes())?;
+	w.write_all(&VPK_VERSION.to_le_bytes())?;
+	w.write_all(&(buf.len() as u32).to_le_bytes())?;
+	w.write_all(&buf)
+}
+
+fn write_str(buf: &mut Vec<u8>, str: &str) {
+	buf.extend_from_slice(str.as_bytes());
+	buf.push(0);
+}
+
+#[test]
+fn test_vpk_writer() {
+	let mut vpk = VpkWriter::new(
+		std::io::Cursor::new(Vec::new()),
+		vec!["lua/autorun/a.lua".to_string(), "addon.json".to_string(), "LICENSE".to_string()],
+	)
+	.unwrap();
+	vpk.write_entry(0, |w| w.write_all(b"print('a')")).unwrap();
+	vpk.write_entry(2, |w| w.write_all(b"MIT")).unwrap();
+	vpk.write_entry(1, |w| w.write_all(b"{}")).unwrap();
+	let vpk = vpk.finish().unwrap().into_inner();
+
+	assert_eq!(&vpk[0..4], &VPK_SIGNATURE.to_le_bytes());
+	assert_eq!(&vpk[4..8], &VPK_VERSION.to_le_bytes());
+
+	let tree_len = u32::from_le_bytes(vpk[8..12].try_into().unwrap()) as usize;
+	let tree = &vpk[12..12 + tree_len];
+	let data = &vpk[12 + tree_len..];
+	assert_eq!(data, b"print('a')MIT{}");
+
+	// Extensionless files are stored under a blank extension and root files under a blank directory
+	let mut expected = Vec::new();
+	for (extension, dir, name, offset, contents) in [
+		(" ", " ", "LICENSE", 10u32, &b"MIT"[..]),
+		("json", " ", "addon", 13, &b"{}"[..]),
+		("lua", "lua/autorun", "a", 0, &b"print('a')"[..]),
+	] {
+		write_str(&mut expected, extension);
+		write_str(&mut expected, dir);
+		write_str(&mut expected, name);
+		expected.extend_from_slice(&crc32fast::hash(contents).to_le_bytes());
+		expected.extend_from_slice(&[0, 0, 0xFF, 0x7F]);
+		expected.extend_from_slice(&offset.to_le_bytes());
+		expected.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+		expected.extend_from_slice(&[0xFF, 0xFF, 0, 0]);
+	}
+	expected.push(0);
+	assert_eq!(tree, expected);
+}
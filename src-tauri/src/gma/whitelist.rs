@@ -165,12 +165,12 @@ fn download_addon_whitelist() -> &'static [&'static str] {
 				));
 			}
 
-			println!("Downloaded up to date addon whitelist: {wildcard:#?}");
+			log::debug!("Downloaded up to date addon whitelist: {wildcard:#?}");
 
 			Ok(&*wildcard.leak())
 		})
 		.map_err(|err| {
-			eprintln!("Failed to download addon whitelist: {:#?}", err);
+			log::warn!("Failed to download addon whitelist: {:#?}", err);
 			err
 		})
 		.unwrap_or(ADDON_WHITELIST_OFFLINE)
@@ -224,6 +224,12 @@ pub fn globber(_wild: &str, _str: &str) -> bool {
 	}
 }
 
+/// Whether Garry's Mod will accept a file at this path (relative to the addon's root, forward slashes) in an addon.
+///
+/// ```ignore
+/// assert!(whitelist::check("lua/autorun/init.lua"));
+/// assert!(!whitelist::check("lua/autorun/init.exe"));
+/// ```
 pub fn check(str: &str) -> bool {
 	let mut str = str.to_string();
 	str.push('\0');
@@ -250,6 +256,12 @@ pub fn filter_default_ignored(str: &str) -> bool {
 	true
 }
 
+/// Whether the path matches any of the ignore patterns. Unlike the patterns users type, these must be null terminated.
+///
+/// ```ignore
+/// assert!(whitelist::is_ignored("lua/hello.lua", &["lua/*.lua\0".to_string()]));
+/// assert!(!whitelist::is_ignored("lua/hello.lua", &["*.txt\0".to_string()]));
+/// ```
 pub fn is_ignored(str: &str, ignore: &[String]) -> bool {
 	if ignore.is_empty() {
 		return false;
@@ -267,6 +279,27 @@ pub fn is_ignored(str: &str, ignore: &[String]) -> bool {
 	false
 }
 
+/// For testing a path as the user types it
+#[tauri::command]
+pub fn whitelist_check(path: String) -> bool {
+	check(&path.replace('\\', "/").to_lowercase())
+}
+
+/// For testing ignore patterns as the user types them
+#[tauri::command]
+pub fn whitelist_is_ignored(path: String, patterns: Vec<String>) -> bool {
+	let patterns: Vec<String> = patterns
+		.into_iter()
+		.filter(|pattern| !pattern.is_empty())
+		.map(|mut pattern| {
+			pattern.push('\0');
+			pattern
+		})
+		.collect();
+
+	is_ignored(&path.replace('\\', "/").to_lowercase(), &patterns)
+}
+
 #[test]
 pub fn test_whitelist() {
 	let good: &'static [&'static str] = &[
@@ -343,3 +376,17 @@ pub fn test_ignore() {
 	assert!(is_ignored("lua/hello.lua", &["lua/*\0".to_string()]));
 	assert!(!is_ignored("lol.txt", &[]));
 }
+
+#[test]
+fn test_whitelist_commands() {
+	assert!(whitelist_check("lua\\autorun\\init.lua".to_string()));
+	assert!(!whitelist_check("lua/autorun/init.exe".to_string()));
+	assert!(whitelist_check("LUA\\Autorun\\Init.LUA".to_string()));
+
+	assert!(whitelist_is_ignored("lua\\hello.lua".to_string(), vec!["lua/*.lua".to_string()]));
+	assert!(whitelist_is_ignored("Lua\\Hello.LUA".to_string(), vec!["lua/*.lua".to_string()]));
+	assert!(!whitelist_is_ignored(
+		"lua/hello.lua".to_string(),
+		vec!["".to_string(), "*.txt".to_string()]
+	));
+}
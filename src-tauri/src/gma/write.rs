@@ -2,10 +2,11 @@ use byteorder::{LittleEndian, WriteBytesExt};
 use lazy_static::lazy_static;
 use rayon::ThreadPool;
 use std::{
-	collections::LinkedList,
+	borrow::Cow,
+	collections::{hash_map::Entry, HashMap, HashSet},
 	fs::{self, File},
-	io::{BufWriter, Write},
-	path::Path,
+	io::{BufWriter, Read, SeekFrom, Write},
+	path::{Path, PathBuf},
 	sync::{atomic::AtomicBool, Arc},
 	time::SystemTime,
 };
@@ -15,27 +16,307 @@ use walkdir::WalkDir;
 
 use crate::{transactions::Transaction, GMAFile, NTStringWriter};
 
-use super::{whitelist, GMAError, GMAMetadata};
+use super::{extract::SEQUENTIAL_READ_BUFFER_SIZE, whitelist, EntryHash, GMAEntry, GMAError, GMAMetadata, GmaCompression};
 
 use super::GMA_HEADER;
 
+/// Roughly how much of the content `estimate_compressed_size` compresses (16 MiB)
+const ESTIMATE_SAMPLE_SIZE: u64 = 16 * 1024 * 1024;
+
 lazy_static! {
 	static ref THREAD_POOL: ThreadPool = thread_pool!();
 }
 
 impl NTStringWriter for BufWriter<File> {}
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+	/// Write entries sorted by path, rather than in whatever order they were read in
+	pub sort_entries: bool,
+	/// Overrides the timestamp in the header, which is otherwise the current time.
+	/// Set this along with `sort_entries` to get byte-identical GMAs from identical inputs
+	pub timestamp: Option<u64>,
+	/// The SteamID64 to record as the author in the header, which is otherwise 0.
+	/// Garry's Mod doesn't read it, but it's there for attributing a repack of someone else's addon
+	pub author: Option<u64>,
+	/// What to checksum the entries with. Anything but CRC32 is recorded in the metadata, and ignored for legacy GMAs
+	pub entry_hash: EntryHash,
+	/// Overrides the version in the header, which is otherwise 1 for legacy metadata and 3 for addon.json metadata.
+	/// Clamped to the versions without entry flags, as nothing here writes them
+	pub version: Option<u8>,
+}
+
 impl GMAFile {
 	pub fn write(&self) -> Result<BufWriter<File>, GMAError> {
-		Ok(BufWriter::new(File::create(&self.path)?))
+		Ok(BufWriter::with_capacity(super::io_buffer_size(), File::create(&self.path)?))
 	}
 
-	pub fn create<P: AsRef<Path>>(&self, src_path: P, transaction: Transaction) -> Result<(), GMAError> {
+	/// Compresses this (uncompressed) GMA into `dest`. Just copies the file for `GmaCompression::None`
+	pub fn compress<P: AsRef<Path>>(&self, dest: P, compression: GmaCompression) -> Result<(), GMAError> {
+		main_thread_forbidden!();
+
+		let (preset, threads) = match compression {
+			GmaCompression::None => {
+				fs::copy(&self.path, dest)?;
+				return Ok(());
+			}
+			GmaCompression::Lzma { preset, threads } => (preset, threads),
+		};
+
+		let stream = if threads > 1 {
+			xz2::stream::MtStreamBuilder::new()
+				.preset(preset)
+				.threads(threads)
+				.check(xz2::stream::Check::Crc32)
+				.encoder()
+		} else {
+			xz2::stream::LzmaOptions::new_preset(preset).and_then(|options| xz2::stream::Stream::new_lzma_encoder(&options))
+		}
+		.map_err(|err| {
+			log::warn!("LZMA error compressing {}: {err:?}", self.path.display());
+			GMAError::LZMA
+		})?;
+
+		// Not `self.path`, which is the compressed file on disk if this GMA was decompressed into memory
+		let mut input = self.read()?;
+		let mut output = xz2::write::XzEncoder::new_stream(BufWriter::with_capacity(super::io_buffer_size(), File::create(dest)?), stream);
+
+		std::io::copy(&mut *input, &mut output)?;
+		output.finish()?.flush()?;
+
+		Ok(())
+	}
+
+	/// Estimates how big `content_path` would be packed and compressed, by compressing an evenly spread sample of its files and extrapolating.
+	/// Never more than the uncompressed size, which is what's returned for `GmaCompression::None`
+	pub fn estimate_compressed_size<P: AsRef<Path>>(content_path: P, compression: GmaCompression) -> Result<u64, GMAError> {
+		main_thread_forbidden!();
+
+		let content_path = content_path.as_ref();
+		let root_path_strip_len = content_path.to_string_lossy().len();
+
+		let mut files = Vec::new();
+		for entry in WalkDir::new(content_path).follow_links(true).into_iter().filter_map(|entry| entry.ok()) {
+			if !entry.file_type().is_file() {
+				continue;
+			}
+
+			let relative_path = entry.path().to_slash_lossy()[root_path_strip_len..]
+				.replace('\\', "/")
+				.trim_matches('/')
+				.to_lowercase();
+			if !whitelist::check(&relative_path) {
+				continue;
+			}
+
+			let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+			files.push((entry.into_path(), relative_path, size));
+		}
+		files.sort_unstable_by(|(_, a, _), (_, b, _)| a.cmp(b));
+
+		// Header, entries table and trailing CRC, give or take the metadata
+		let overhead: u64 = 64 + files.iter().map(|(_, relative_path, _)| relative_path.len() as u64 + 17).sum::<u64>();
+		let uncompressed = overhead + files.iter().map(|(.., size)| size).sum::<u64>();
+
+		let preset = match compression {
+			GmaCompression::None => return Ok(uncompressed),
+			GmaCompression::Lzma { preset, .. } => preset,
+		};
+
+		struct ByteCounter(u64);
+		impl Write for ByteCounter {
+			fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+				self.0 += buf.len() as u64;
+				Ok(buf.len())
+			}
+
+			fn flush(&mut self) -> std::io::Result<()> {
+				Ok(())
+			}
+		}
+
+		let stream = xz2::stream::LzmaOptions::new_preset(preset)
+			.and_then(|options| xz2::stream::Stream::new_lzma_encoder(&options))
+			.map_err(|err| {
+				log::warn!("LZMA error estimating {}: {err:?}", content_path.display());
+				GMAError::LZMA
+			})?;
+		let mut output = xz2::write::XzEncoder::new_stream(ByteCounter(0), stream);
+
+		// Sample a file every `stride` bytes through the content, so big files are as likely to be picked as they are to dominate the output
+		let stride = u64::max(uncompressed / ESTIMATE_SAMPLE_SIZE, 1);
+		let mut next_sample = 0;
+		let mut position = 0;
+		let mut sampled: u64 = 0;
+		for (path, _, size) in files {
+			position += size;
+			if position < next_sample {
+				continue;
+			}
+			next_sample = position + stride;
+
+			if let Ok(f) = File::open(path) {
+				sampled += std::io::copy(&mut f.take(ESTIMATE_SAMPLE_SIZE / 16), &mut output).unwrap_or(0);
+			}
+		}
+
+		if sampled == 0 {
+			return Ok(uncompressed);
+		}
+
+		let compressed = output.finish()?.0;
+
+		Ok(u64::min(
+			(uncompressed as f64 * (compressed as f64 / sampled as f64)).ceil() as u64,
+			uncompressed,
+		))
+	}
+
+	/// The metadata as it will be written, with `entry_hash` recorded in it
+	fn header_metadata(&self, entry_hash: EntryHash) -> GMAMetadata {
+		let mut metadata = self.metadata.clone().expect("Expected metadata to be set");
+		metadata.set_entry_hash(entry_hash);
+		metadata
+	}
+
+	fn write_header(&self, f: &mut BufWriter<File>, metadata: &GMAMetadata, options: &WriteOptions) -> Result<(), GMAError> {
+		let (version, title, description) = match metadata {
+			GMAMetadata::Legacy { title, description } => (1, title.as_str(), Cow::Borrowed(description.as_str())),
+			GMAMetadata::Standard { title, .. } => (3, title.as_str(), Cow::Owned(serde_json::ser::to_string(metadata).unwrap())),
+		};
+		let version = options
+			.version
+			.map_or(version, |version| version.clamp(1, super::GMA_VERSION_ENTRY_FLAGS - 1));
+
+		f.write_all(GMA_HEADER)?;
+
+		f.write_u8(version)?; // gma version
+
+		// steamid [unused]
+		f.write_u64::<LittleEndian>(options.author.unwrap_or(0))?;
+
+		// timestamp [unused]
+		f.write_u64::<LittleEndian>(
+			options
+				.timestamp
+				.unwrap_or_else(|| match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+					Ok(unix) => unix.as_secs(),
+					Err(_) => 0,
+				}),
+		)?;
+
+		// required content, terminated by an empty string
+		if version > 1 {
+			for content in self.required_content.iter() {
+				f.write_nt_string(content)?;
+			}
+			f.write_u8(0)?;
+		}
+
+		// addon name
+		f.write_nt_string(title)?;
+
+		// addon description (addon.json for standard GMAs)
+		f.write_nt_string(&description)?;
+
+		// addon author [unused]
+		f.write_nt_string("Author Name")?;
+
+		// addon version [unused]
+		f.write_i32::<LittleEndian>(1)?;
+
+		Ok(())
+	}
+
+	/// Writes the file table, the file contents and the trailing CRC. Entries are `(path, contents, hash)`.
+	/// With a transaction, progress is reported by how much of the contents has been written
+	fn write_entries(f: &mut BufWriter<File>, entries: &[(Box<[u8]>, Box<[u8]>, u32)], transaction: Option<&Transaction>) -> Result<(), GMAError> {
+		for (i, (path, contents, crc32)) in entries.iter().enumerate() {
+			f.write_u32::<LittleEndian>(i as u32 + 1)?;
+			f.write_all(path)?;
+			f.write_u8(0)?;
+			f.write_i64::<LittleEndian>(contents.len() as i64)?;
+			f.write_u32::<LittleEndian>(*crc32)?;
+		}
+
+		f.write_u32::<LittleEndian>(0)?;
+
+		let total = entries.iter().map(|(_, contents, _)| contents.len()).sum::<usize>().max(1) as f64;
+		let mut written_total = 0;
+		for (_, contents, _) in entries.iter() {
+			f.write_all(contents)?;
+
+			if let Some(transaction) = transaction {
+				written_total += contents.len();
+				transaction.progress(written_total as f64 / total);
+			}
+		}
+
+		let written = f.buffer();
+
+		let mut crc32 = crc32fast::Hasher::new();
+		crc32.reset();
+		crc32.update(written);
+		let crc32 = crc32.finalize();
+
+		f.write_u32::<LittleEndian>(crc32)?;
+
+		f.flush()?;
+
+		Ok(())
+	}
+
+	/// Writes a GMA from entries that are already in memory, such as generated files, without touching the disk for anything but the GMA itself.
+	/// Paths are normalized the same way `create` normalizes them; checking them against the whitelist is up to the caller
+	pub fn create_from_memory(&self, entries: Vec<(String, Vec<u8>)>, options: WriteOptions) -> Result<(), GMAError> {
+		let metadata = self.header_metadata(options.entry_hash);
+		let entry_hash = metadata.entry_hash();
+
+		let mut files = Vec::with_capacity(entries.len());
+		let mut dedup: HashMap<String, String> = HashMap::new();
+		let mut duplicates = Vec::new();
+
+		for (original_path, contents) in entries {
+			let original_path = original_path.replace('\\', "/").trim_matches('/').to_owned();
+			let relative_path = original_path.to_lowercase();
+
+			match dedup.entry(relative_path.clone()) {
+				Entry::Occupied(existing) => {
+					duplicates.push(existing.get().to_owned());
+					duplicates.push(original_path);
+				}
+				Entry::Vacant(vacant) => {
+					vacant.insert(original_path);
+					let hash = entry_hash.hash(&contents);
+					files.push((relative_path.into_bytes().into_boxed_slice(), contents.into_boxed_slice(), hash));
+				}
+			}
+		}
+
+		if !duplicates.is_empty() {
+			return Err(GMAError::DuplicateEntries(duplicates));
+		}
+
+		if options.sort_entries {
+			files.sort_unstable_by(|(a, ..), (b, ..)| a.cmp(b));
+		}
+
 		let mut f = self.write()?;
+		self.write_header(&mut f, &metadata, &options)?;
+		GMAFile::write_entries(&mut f, &files, None)?;
 
+		Ok(())
+	}
+
+	pub fn create<P: AsRef<Path>>(&self, src_path: P, transaction: Transaction) -> Result<(), GMAError> {
+		self.create_with_options(src_path, WriteOptions::default(), transaction)
+	}
+
+	pub fn create_with_options<P: AsRef<Path>>(&self, src_path: P, options: WriteOptions, transaction: Transaction) -> Result<(), GMAError> {
 		let src_path = src_path.as_ref();
 
-		let metadata = self.metadata.as_ref().expect("Expected metadata to be set");
+		let metadata = self.header_metadata(options.entry_hash);
+		let entry_hash = metadata.entry_hash();
 		let ignore = metadata.ignore().map(|ignore| {
 			ignore
 				.iter()
@@ -48,41 +329,69 @@ impl GMAFile {
 				.into_boxed_slice()
 		});
 
-		let (title, addon_json) = match metadata {
-			GMAMetadata::Legacy { title, .. } => (title.as_str(), None),
-			GMAMetadata::Standard { title, .. } => (title.as_str(), Some(metadata)),
-		};
+		let files = {
+			let root_path_strip_len = src_path.to_string_lossy().len();
 
-		f.write_all(GMA_HEADER)?;
+			let mut files = Vec::new();
+			let mut dedup: HashMap<String, String> = HashMap::new();
+			let mut duplicates = Vec::new();
 
-		f.write_u8(3)?; // gma version
+			for entry in WalkDir::new(src_path).follow_links(true).into_iter().filter_map(|entry| entry.ok()) {
+				if !entry.file_type().is_file() {
+					continue;
+				}
 
-		// steamid [unused]
-		f.write_u64::<LittleEndian>(0)?;
+				let path = entry.into_path();
 
-		// timestamp [unused]
-		f.write_u64::<LittleEndian>(match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-			Ok(unix) => unix.as_secs(),
-			Err(_) => 0,
-		})?;
+				// Entry names are stored as UTF-8, and a lossy conversion would silently rename the file
+				if path.strip_prefix(src_path).map_or(false, |relative| relative.to_str().is_none()) {
+					return Err(GMAError::InvalidPath(path));
+				}
 
-		// required content [unused]
-		f.write_u8(0)?;
+				// Garry's Mod only understands forward slashes, and a backslash can still sneak in as part of a file name
+				let original_path = path.to_slash_lossy()[root_path_strip_len..]
+					.replace('\\', "/")
+					.trim_matches('/')
+					.to_owned();
+				let relative_path = original_path.to_lowercase();
 
-		// addon name
-		f.write_nt_string(title)?;
+				if !whitelist::check(&relative_path) {
+					transaction.data(("ERR_WHITELIST", relative_path));
+					continue;
+				}
 
-		// addon description
-		match addon_json {
-			Some(addon_json) => f.write_nt_string(serde_json::ser::to_string(addon_json).as_deref().unwrap())?,
-			None => f.write_nt_string("Description")?,
-		};
+				if let Some(ref ignore) = ignore {
+					if whitelist::is_ignored(&relative_path, ignore) {
+						continue;
+					}
+				}
 
-		// addon author [unused]
-		f.write_nt_string("Author Name")?;
+				// Paths are lowercased in the GMA, so two differently cased files would collide and the game can't resolve them
+				match dedup.entry(relative_path.clone()) {
+					Entry::Occupied(existing) => {
+						duplicates.push(existing.get().to_owned());
+						duplicates.push(original_path);
+					}
+					Entry::Vacant(vacant) => {
+						vacant.insert(original_path);
+						files.push((path, relative_path));
+					}
+				}
+			}
 
-		// addon version [unused]
-		f.write_i32::<LittleEndian>(1)?;
+			if !duplicates.is_empty() {
+				return Err(GMAError::DuplicateEntries(duplicates));
+			}
+
+			if options.sort_entries {
+				files.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+			}
+
+			files
+		};
+
+		// Read and hash everything in parallel first, as its own phase, since the file table needs every hash before any contents can be written
+		transaction.status("PUBLISH_HASHING");
 
 		// file list
 		let (error, rx, total) = {
@@ -90,30 +399,8 @@ impl GMAFile {
 
 			let (tx, rx) = crossbeam::channel::unbounded();
 
-			let root_path_strip_len = src_path.to_string_lossy().len();
-
-			let mut total = 0.;
-			for (path, relative_path) in WalkDir::new(src_path).follow_links(true).into_iter().filter_map(|entry| {
-				entry.ok().and_then(|entry| {
-					if entry.file_type().is_file() {
-						let path = entry.into_path();
-
-						let relative_path = path.to_slash_lossy()[root_path_strip_len..].trim_matches('/').to_lowercase();
-
-						if whitelist::check(&relative_path) {
-							if let Some(ref ignore) = ignore {
-								if whitelist::is_ignored(&relative_path, ignore) {
-									return None;
-								}
-							}
-							return Some((path, relative_path));
-						} else {
-							transaction.data(("ERR_WHITELIST", relative_path));
-						}
-					}
-					None
-				})
-			}) {
+			let total = files.len() as f64;
+			for (index, (path, relative_path)) in files.into_iter().enumerate() {
 				if error.load(std::sync::atomic::Ordering::Acquire) {
 					break;
 				}
@@ -132,59 +419,633 @@ impl GMAFile {
 						}
 					};
 
-					let mut crc32 = crc32fast::Hasher::new();
-					crc32.reset();
-					crc32.update(&contents);
-					let crc32 = crc32.finalize();
+					let hash = entry_hash.hash(&contents);
 
-					tx.send((relative_path.into_bytes().into_boxed_slice(), contents.into_boxed_slice(), crc32))
+					tx.send((index, relative_path.into_bytes().into_boxed_slice(), contents.into_boxed_slice(), hash))
 						.unwrap();
 				});
-
-				total += 1.;
 			}
 
 			(error, rx, total)
 		};
 
-		let mut entries_buf = LinkedList::new();
+		// Files are read in parallel, so they arrive in whatever order they finish in
+		let mut entries_buf = Vec::new();
 
-		let mut i = 0;
 		let mut i_f: f64 = 0.;
-		while let Ok((path, contents, crc32)) = rx.recv() {
+		while let Ok(entry) = rx.recv() {
+			entries_buf.push(entry);
+
+			i_f += 1.;
+			transaction.progress(i_f / total);
+		}
+
+		if options.sort_entries {
+			entries_buf.sort_unstable_by_key(|(index, ..)| *index);
+		}
+
+		if Arc::try_unwrap(error).unwrap().into_inner() {
+			return Err(GMAError::IOError);
+		}
+
+		let entries: Vec<_> = entries_buf.into_iter().map(|(_, path, contents, hash)| (path, contents, hash)).collect();
+
+		transaction.progress_reset();
+		transaction.status("PUBLISH_PACKING");
+
+		let mut f = self.write()?;
+		self.write_header(&mut f, &metadata, &options)?;
+		GMAFile::write_entries(&mut f, &entries, Some(&transaction))?;
+
+		Ok(())
+	}
+
+	/// Combines several GMAs into a new one at `dest`, streaming each input's entries straight into it.
+	/// Fails with `GMAError::DuplicateEntries` if two inputs contain the same path
+	pub fn merge<P: AsRef<Path>>(
+		mut inputs: Vec<GMAFile>,
+		dest: P,
+		mut metadata: GMAMetadata,
+		transaction: &Transaction,
+	) -> Result<GMAFile, GMAError> {
+		main_thread_forbidden!();
+
+		let mut seen = HashSet::new();
+		let mut duplicates = Vec::new();
+		for input in inputs.iter_mut() {
+			input.entries()?;
+			for path in input.entries.as_ref().unwrap().keys() {
+				if !seen.insert(path.clone()) {
+					duplicates.push(path.clone());
+				}
+			}
+		}
+
+		if !duplicates.is_empty() {
+			duplicates.sort_unstable();
+			duplicates.dedup();
+			return Err(GMAError::DuplicateEntries(duplicates));
+		}
+
+		let inputs: Vec<(&GMAFile, Vec<&GMAEntry>)> = inputs
+			.iter()
+			.map(|input| {
+				let mut entries: Vec<&GMAEntry> = input.entries.as_ref().unwrap().values().collect();
+				entries.sort_unstable_by_key(|entry| entry.index);
+				(input, entries)
+			})
+			.collect();
+
+		// Their sizes in the table would be wrong, and the output has nowhere to store the flag anyway
+		if let Some((input, entry)) = inputs
+			.iter()
+			.find_map(|(input, entries)| entries.iter().find(|entry| entry.compressed).map(|entry| (input, entry)))
+		{
+			log::warn!("Can't merge individually compressed entry {} from {}", entry.path, input.path.display());
+			return Err(GMAError::FormatError);
+		}
+
+		// The checksums are copied as-is, so they have to agree on how they were computed
+		let entry_hash = inputs.first().map(|(input, _)| input.entry_hash()).unwrap_or_default();
+		if let Some((input, _)) = inputs.iter().find(|(input, _)| input.entry_hash() != entry_hash) {
+			log::warn!(
+				"Can't merge {} as its entries are checksummed with {:?} rather than {:?}",
+				input.path.display(),
+				input.entry_hash(),
+				entry_hash
+			);
+			return Err(GMAError::FormatError);
+		}
+		metadata.set_entry_hash(entry_hash);
+
+		let output = GMAFile::new(dest, metadata);
+		let mut f = output.write()?;
+		output.write_header(&mut f, output.metadata.as_ref().unwrap(), &WriteOptions::default())?;
+
+		let mut i: u32 = 0;
+		for entry in inputs.iter().flat_map(|(_, entries)| entries.iter()) {
 			i += 1;
+			f.write_u32::<LittleEndian>(i)?;
+			f.write_all(entry.path.as_bytes())?;
+			f.write_u8(0)?;
+			f.write_i64::<LittleEndian>(entry.size as i64)?;
+			f.write_u32::<LittleEndian>(entry.crc)?;
+		}
+		f.write_u32::<LittleEndian>(0)?;
+
+		let total_f = inputs.iter().map(|(_, entries)| entries.len()).sum::<usize>() as f64;
+		let mut written: usize = 0;
+
+		for (input, entries) in inputs.iter() {
+			let mut handle = input.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+			handle.seek(SeekFrom::Start(input.pointers.entries))?;
+
+			let mut cursor = 0;
+			for entry in entries.iter() {
+				if transaction.aborted() {
+					return Err(GMAError::Cancelled);
+				}
+
+				let gap = entry.index - cursor;
+				if gap > 0 {
+					std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+				}
 
-			f.write_u32::<LittleEndian>(i as u32)?;
-			f.write_all(&path)?;
+				crate::stream_bytes(&mut *handle, &mut f, entry.size as usize)?;
+
+				cursor = entry.index + entry.size;
+
+				written += 1;
+				transaction.progress(written as f64 / total_f);
+			}
+		}
+
+		// addon crc [unused]
+		f.write_u32::<LittleEndian>(0)?;
+		f.flush()?;
+
+		Ok(output)
+	}
+
+	/// Writes a copy of this GMA to `dest` with one entry's contents swapped for `new_data`.
+	/// Every other entry is streamed across unchanged, which is much faster than extracting and repacking for a one-file edit
+	pub fn replace_entry<P: AsRef<Path>>(&mut self, entry_path: &str, new_data: &[u8], dest: P) -> Result<GMAFile, GMAError> {
+		main_thread_forbidden!();
+
+		self.entries()?;
+
+		let entry_path = entry_path.replace('\\', "/").trim_matches('/').to_owned();
+		if !self.entries.as_ref().unwrap().contains_key(&entry_path) {
+			return Err(GMAError::EntryNotFound);
+		}
+
+		let mut entries: Vec<&GMAEntry> = self.entries.as_ref().unwrap().values().collect();
+		entries.sort_unstable_by_key(|entry| entry.index);
+
+		// Same as merging, the output has nowhere to store the flag
+		if let Some(entry) = entries.iter().find(|entry| entry.compressed && entry.path != entry_path) {
+			log::warn!("Can't copy individually compressed entry {} from {}", entry.path, self.path.display());
+			return Err(GMAError::FormatError);
+		}
+
+		let entry_hash = self.entry_hash();
+		let new_crc = entry_hash.hash(new_data);
+
+		let mut output = GMAFile::new(dest, self.metadata.clone().expect("Expected metadata to be set"));
+		output.required_content = self.required_content.clone();
+
+		// Only the one entry changes, so the header should look like it came from the same pack
+		let options = WriteOptions {
+			timestamp: self.timestamp,
+			author: self.author,
+			entry_hash,
+			version: Some(self.version),
+			..Default::default()
+		};
+
+		let mut f = output.write()?;
+		output.write_header(&mut f, output.metadata.as_ref().unwrap(), &options)?;
+
+		for (i, entry) in entries.iter().enumerate() {
+			let (size, crc) = if entry.path == entry_path {
+				(new_data.len() as u64, new_crc)
+			} else {
+				(entry.size, entry.crc)
+			};
+
+			f.write_u32::<LittleEndian>(i as u32 + 1)?;
+			f.write_all(entry.path.as_bytes())?;
 			f.write_u8(0)?;
-			f.write_i64::<LittleEndian>(contents.len() as i64)?;
-			f.write_u32::<LittleEndian>(crc32)?;
+			f.write_i64::<LittleEndian>(size as i64)?;
+			f.write_u32::<LittleEndian>(crc)?;
+		}
+		f.write_u32::<LittleEndian>(0)?;
 
-			entries_buf.push_back(contents);
+		let mut handle = self.read_with_capacity(SEQUENTIAL_READ_BUFFER_SIZE)?;
+		handle.seek(SeekFrom::Start(self.pointers.entries))?;
 
-			i_f += 1.;
-			transaction.progress(i_f / total);
+		let mut cursor = 0;
+		for entry in entries.iter() {
+			let gap = entry.index - cursor;
+			if gap > 0 {
+				std::io::copy(&mut (&mut *handle).take(gap), &mut std::io::sink())?;
+			}
+
+			if entry.path == entry_path {
+				std::io::copy(&mut (&mut *handle).take(entry.size), &mut std::io::sink())?;
+				f.write_all(new_data)?;
+			} else {
+				crate::stream_bytes(&mut *handle, &mut f, entry.size as usize)?;
+			}
+
+			cursor = entry.index + entry.size;
 		}
 
+		// addon crc [unused]
 		f.write_u32::<LittleEndian>(0)?;
+		f.flush()?;
+
+		Ok(output)
+	}
+}
+
+#[tauri::command]
+pub fn estimate_compressed_size(content_path: PathBuf, compression: GmaCompression) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || match GMAFile::estimate_compressed_size(content_path, compression) {
+		Ok(size) => transaction.finished(size),
+		Err(error) => transaction.error(error.to_string(), turbonone!()),
+	});
+
+	id
+}
 
-		for contents in entries_buf.into_iter() {
-			f.write_all(&contents)?;
+#[tauri::command]
+pub fn merge_gmas(paths: Vec<PathBuf>, dest: PathBuf, metadata: GMAMetadata) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || {
+		let result = paths
+			.into_iter()
+			.map(GMAFile::open)
+			.collect::<Result<Vec<_>, GMAError>>()
+			.and_then(|inputs| GMAFile::merge(inputs, &dest, metadata, &transaction));
+
+		match result {
+			Ok(_) => transaction.finished(dest),
+			Err(GMAError::Cancelled) => {}
+			Err(error) => transaction.error(error.to_string(), turbonone!()),
 		}
+	});
 
-		let written = f.buffer();
+	id
+}
 
-		let mut crc32 = crc32fast::Hasher::new();
-		crc32.reset();
-		crc32.update(written);
-		let crc32 = crc32.finalize();
+#[test]
+fn test_write_legacy() {
+	let src_path = std::env::temp_dir().join("gmpublisher_test_write_legacy");
+	fs::create_dir_all(src_path.join("lua/autorun")).unwrap();
+	fs::write(src_path.join("lua/autorun/test.lua"), "print('hello')").unwrap();
 
-		f.write_u32::<LittleEndian>(crc32)?;
+	let path = std::env::temp_dir().join("gmpublisher_test_write_legacy.gma");
+	GMAFile::new(
+		&path,
+		GMAMetadata::Legacy {
+			title: "Legacy Addon".to_string(),
+			description: "An addon for very old servers".to_string(),
+		},
+	)
+	.create(&src_path, crate::transactions::new())
+	.unwrap();
 
-		if Arc::try_unwrap(error).unwrap().into_inner() {
-			return Err(GMAError::IOError);
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert_eq!(gma.version, 1);
+
+	gma.entries().unwrap();
+	match gma.metadata.as_ref().unwrap() {
+		GMAMetadata::Legacy { title, description } => {
+			assert_eq!(title, "Legacy Addon");
+			assert_eq!(description, "An addon for very old servers");
 		}
+		GMAMetadata::Standard { .. } => panic!("Expected legacy metadata"),
+	}
+	assert!(gma.entries.as_ref().unwrap().contains_key("lua/autorun/test.lua"));
 
-		Ok(())
+	fs::remove_dir_all(src_path).ok();
+	fs::remove_file(path).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_rejects_non_utf8_paths() {
+	use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+	let src_path = std::env::temp_dir().join("gmpublisher_test_write_non_utf8");
+	let bad_path = src_path.join("lua").join(OsStr::from_bytes(b"caf\xE9.lua"));
+	fs::create_dir_all(src_path.join("lua")).unwrap();
+	fs::write(&bad_path, "print('hello')").unwrap();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_write_non_utf8.gma");
+	let result = GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Test Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec![],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	)
+	.create(&src_path, crate::transactions::new());
+
+	assert!(matches!(result, Err(GMAError::InvalidPath(ref path)) if *path == bad_path));
+
+	fs::remove_dir_all(src_path).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_compress() {
+	let path = std::env::temp_dir().join("gmpublisher_test_compress.gma");
+	fs::write(
+		&path,
+		super::read::test_gma("Test Addon", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('a')")]),
+	)
+	.unwrap();
+
+	let gma = GMAFile::open(&path).unwrap();
+
+	for threads in [1, 4] {
+		let compressed = std::env::temp_dir().join(format!("gmpublisher_test_compress_{}.gma.lzma", threads));
+		gma.compress(&compressed, GmaCompression::Lzma { preset: 6, threads }).unwrap();
+
+		assert_eq!(GMAFile::peek_format(&compressed).unwrap(), super::GmaFormat::Lzma);
+
+		let mut decompressed = GMAFile::decompress(&compressed, super::DEFAULT_LZMA_MEMLIMIT, crate::transactions::new()).unwrap();
+		decompressed.entries().unwrap();
+		assert!(decompressed.entries.as_ref().unwrap().contains_key("lua/autorun/a.lua"));
+
+		fs::remove_file(compressed).ok();
 	}
+
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_write_sorted_is_reproducible() {
+	let src_path = std::env::temp_dir().join("gmpublisher_test_write_sorted");
+	for file in ["lua/autorun/b.lua", "lua/autorun/a.lua", "materials/c.vmt", "lua/d.lua"] {
+		let path = src_path.join(file);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, file).unwrap();
+	}
+
+	let options = WriteOptions {
+		sort_entries: true,
+		timestamp: Some(0),
+		..Default::default()
+	};
+
+	let gma = |path: &Path| {
+		GMAFile::new(
+			path,
+			GMAMetadata::Standard {
+				title: "Test Addon".to_string(),
+				addon_type: "tool".to_string(),
+				description: String::new(),
+				tags: vec![],
+				ignore: vec![],
+				extra: Default::default(),
+			},
+		)
+		.create_with_options(&src_path, options, crate::transactions::new())
+		.unwrap();
+	};
+
+	let path_a = std::env::temp_dir().join("gmpublisher_test_write_sorted_a.gma");
+	let path_b = std::env::temp_dir().join("gmpublisher_test_write_sorted_b.gma");
+	gma(&path_a);
+	gma(&path_b);
+
+	assert_eq!(fs::read(&path_a).unwrap(), fs::read(&path_b).unwrap());
+
+	let mut written = GMAFile::open(&path_a).unwrap();
+	written.entries().unwrap();
+	let mut entries: Vec<_> = written.entries.as_ref().unwrap().values().collect();
+	entries.sort_unstable_by_key(|entry| entry.index);
+	assert_eq!(
+		entries.into_iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(),
+		["lua/autorun/a.lua", "lua/autorun/b.lua", "lua/d.lua", "materials/c.vmt"]
+	);
+
+	fs::remove_dir_all(src_path).ok();
+	fs::remove_file(path_a).ok();
+	fs::remove_file(path_b).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_normalizes_backslashes() {
+	let src_path = std::env::temp_dir().join("gmpublisher_test_write_backslashes");
+	fs::create_dir_all(&src_path).unwrap();
+	fs::write(src_path.join("materials\\foo.vmt"), "\"VertexLitGeneric\" {}").unwrap();
+
+	let path = std::env::temp_dir().join("gmpublisher_test_write_backslashes.gma");
+	GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Test Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec![],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	)
+	.create(&src_path, crate::transactions::new())
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.entries().unwrap();
+	let entries = gma.entries.as_ref().unwrap();
+	assert!(entries.contains_key("materials/foo.vmt"));
+	assert!(!entries.keys().any(|path| path.contains('\\')));
+
+	fs::remove_dir_all(src_path).ok();
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_create_from_memory() {
+	let path = std::env::temp_dir().join("gmpublisher_test_create_from_memory.gma");
+	let gma = GMAFile::new(
+		&path,
+		GMAMetadata::Standard {
+			title: "Generated Addon".to_string(),
+			addon_type: "tool".to_string(),
+			description: String::new(),
+			tags: vec![],
+			ignore: vec![],
+			extra: Default::default(),
+		},
+	);
+
+	let result = gma.create_from_memory(
+		vec![("lua/A.lua".to_string(), b"a".to_vec()), ("lua/a.lua".to_string(), b"b".to_vec())],
+		WriteOptions::default(),
+	);
+	assert!(matches!(result, Err(GMAError::DuplicateEntries(_))));
+
+	gma.create_from_memory(
+		vec![
+			("materials\\b.vmt".to_string(), b"\"VertexLitGeneric\" {}".to_vec()),
+			("lua/autorun/A.lua".to_string(), b"print('a')".to_vec()),
+		],
+		WriteOptions {
+			sort_entries: true,
+			timestamp: Some(0),
+			..Default::default()
+		},
+	)
+	.unwrap();
+
+	let mut written = GMAFile::open(&path).unwrap();
+	written.entries().unwrap();
+	let entries = written.entries.as_ref().unwrap();
+	assert_eq!(entries["lua/autorun/a.lua"].crc, crc32fast::hash(b"print('a')"));
+	assert!(entries.contains_key("materials/b.vmt"));
+
+	fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_merge() {
+	let path_a = std::env::temp_dir().join("gmpublisher_test_merge_a.gma");
+	let path_b = std::env::temp_dir().join("gmpublisher_test_merge_b.gma");
+	let path_c = std::env::temp_dir().join("gmpublisher_test_merge_c.gma");
+	fs::write(
+		&path_a,
+		super::read::test_gma(
+			"A",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("materials/a.vmt", b"")],
+		),
+	)
+	.unwrap();
+	fs::write(
+		&path_b,
+		super::read::test_gma("B", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/b.lua", b"print('b')")]),
+	)
+	.unwrap();
+	fs::write(
+		&path_c,
+		super::read::test_gma("C", r#"{"type":"tool","tags":[]}"#, &[("lua/autorun/a.lua", b"print('c')")]),
+	)
+	.unwrap();
+
+	let metadata = GMAMetadata::Standard {
+		title: "Merged Addon".to_string(),
+		addon_type: "tool".to_string(),
+		description: String::new(),
+		tags: vec![],
+		ignore: vec![],
+		extra: Default::default(),
+	};
+
+	let dest = std::env::temp_dir().join("gmpublisher_test_merge.gma");
+
+	let result = GMAFile::merge(
+		vec![GMAFile::open(&path_a).unwrap(), GMAFile::open(&path_c).unwrap()],
+		&dest,
+		metadata.clone(),
+		&crate::transactions::new(),
+	);
+	assert!(matches!(result, Err(GMAError::DuplicateEntries(ref paths)) if paths == &["lua/autorun/a.lua"]));
+
+	GMAFile::merge(
+		vec![GMAFile::open(&path_a).unwrap(), GMAFile::open(&path_b).unwrap()],
+		&dest,
+		metadata,
+		&crate::transactions::new(),
+	)
+	.unwrap();
+
+	let mut merged = GMAFile::open(&dest).unwrap();
+	assert!(merged.verify(&crate::transactions::new()).unwrap().passed);
+	assert_eq!(merged.metadata.as_ref().unwrap().title(), "Merged Addon");
+
+	let entries = merged.entries.as_ref().unwrap();
+	assert_eq!(entries.len(), 3);
+	assert_eq!(entries["lua/autorun/b.lua"].size, 10);
+	assert_eq!(entries["materials/a.vmt"].size, 0);
+
+	for path in [path_a, path_b, path_c, dest] {
+		fs::remove_file(path).ok();
+	}
+}
+
+#[test]
+fn test_estimate_compressed_size() {
+	let src_path = std::env::temp_dir().join("gmpublisher_test_estimate_compressed_size");
+	fs::remove_dir_all(&src_path).ok();
+	fs::create_dir_all(src_path.join("lua/autorun")).unwrap();
+	fs::write(src_path.join("lua/autorun/a.lua"), "print('hello')\n".repeat(4096)).unwrap();
+	fs::write(src_path.join("lua/autorun/b.lua"), "print('world')\n".repeat(4096)).unwrap();
+
+	let uncompressed = GMAFile::estimate_compressed_size(&src_path, GmaCompression::None).unwrap();
+	assert!(uncompressed > 2 * 15 * 4096);
+
+	let compressed = GMAFile::estimate_compressed_size(&src_path, GmaCompression::Lzma { preset: 6, threads: 1 }).unwrap();
+	assert!(compressed > 0 && compressed < uncompressed / 10);
+
+	fs::remove_dir_all(src_path).ok();
+}
+
+#[test]
+fn test_replace_entry() {
+	let path = std::env::temp_dir().join("gmpublisher_test_replace_entry.gma");
+	let dest = std::env::temp_dir().join("gmpublisher_test_replace_entry_out.gma");
+
+	fs::write(
+		&path,
+		super::read::test_gma(
+			"Test Addon",
+			r#"{"type":"tool","tags":[]}"#,
+			&[("lua/autorun/a.lua", b"print('a')"), ("lua/autorun/b.lua", b"print('b')")],
+		),
+	)
+	.unwrap();
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	assert!(matches!(gma.replace_entry("lua/autorun/c.lua", b"", &dest), Err(GMAError::EntryNotFound)));
+	gma.replace_entry("lua/autorun/a.lua", b"print('replaced')", &dest).unwrap();
+
+	let mut replaced = GMAFile::open(&dest).unwrap();
+	assert!(replaced.verify(&crate::transactions::new()).unwrap().passed);
+	assert_eq!(replaced.metadata.as_ref().unwrap().title(), "Test Addon");
+	assert_eq!(replaced.version, gma.version);
+	assert_eq!(replaced.timestamp, gma.timestamp);
+	assert_eq!(replaced.author, gma.author);
+	assert_eq!(replaced.read_entry_prefix("lua/autorun/a.lua", 64).unwrap(), b"print('replaced')");
+	assert_eq!(replaced.read_entry_prefix("lua/autorun/b.lua", 64).unwrap(), b"print('b')");
+
+	fs::remove_file(path).ok();
+	fs::remove_file(dest).ok();
+}
+
+#[test]
+fn test_write_author_and_timestamp() {
+	let path = std::env::temp_dir().join("gmpublisher_test_write_author_and_timestamp.gma");
+
+	GMAFile::new(
+		&path,
+		GMAMetadata::Legacy {
+			title: "Test Addon".to_string(),
+			description: String::new(),
+		},
+	)
+	.create_from_memory(
+		vec![("lua/autorun/a.lua".to_string(), b"print('a')".to_vec())],
+		WriteOptions {
+			author: Some(76561197960287930),
+			timestamp: Some(1234),
+			..Default::default()
+		},
+	)
+	.unwrap();
+
+	// GMAD, then the version, then the header's steamid and timestamp
+	let written = fs::read(&path).unwrap();
+	assert_eq!(&written[5..13], &76561197960287930u64.to_le_bytes());
+	assert_eq!(&written[13..21], &1234u64.to_le_bytes());
+
+	let mut gma = GMAFile::open(&path).unwrap();
+	gma.metadata().unwrap();
+	assert_eq!(gma.timestamp, Some(1234));
+
+	fs::remove_file(path).ok();
 }
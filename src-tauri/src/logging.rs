@@ -71,6 +71,43 @@ macro_rules! eprintln {
 	};
 }
 
+/// Sends `log` records to stdout/stderr and the log files, the same way `println!` and `eprintln!` do
+struct Logger;
+impl log::Log for Logger {
+	fn enabled(&self, metadata: &log::Metadata) -> bool {
+		metadata.level() <= log::max_level() && metadata.target().starts_with("gmpublisher")
+	}
+
+	fn log(&self, record: &log::Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+
+		let log = format!("[{}] {}", record.level(), record.args());
+		if record.level() <= log::Level::Warn {
+			std::eprintln!("{}", &log);
+			ignore! { LOG_CHANNEL.send(LogMessage::Stderr(log)) };
+		} else {
+			std::println!("{}", &log);
+			ignore! { LOG_CHANNEL.send(LogMessage::Stdout(log)) };
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// Debug records are only kept in debug builds
+pub fn init_logger() {
+	static LOGGER: Logger = Logger;
+	if log::set_logger(&LOGGER).is_ok() {
+		log::set_max_level(if cfg!(debug_assertions) {
+			log::LevelFilter::Debug
+		} else {
+			log::LevelFilter::Info
+		});
+	}
+}
+
 pub fn panic(panic: &PanicInfo) {
 	use std::io::Write;
 
@@ -79,6 +79,7 @@ fn main() {
 	}
 
 	std::panic::set_hook(Box::new(logging::panic));
+	logging::init_logger();
 
 	rayon::ThreadPoolBuilder::new().num_threads(*crate::NUM_THREADS).build_global().unwrap();
 
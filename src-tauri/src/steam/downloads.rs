@@ -9,7 +9,7 @@ use std::{
 use steamworks::{ClientManager, ItemState, PublishedFileId, QueryResults, UGC};
 
 use crate::{
-	gma::{ExtractDestination, ExtractGMAMut},
+	gma::{ExtractDestination, ExtractGMAMut, GmaFormat},
 	transaction,
 	transactions::Transaction,
 	webview_emit, GMAFile, GMOD_APP_ID,
@@ -113,11 +113,10 @@ impl Downloads {
 					return transaction.error("ERR_DOWNLOAD_MISSING", turbonone!());
 				}
 			} else if folder.is_file() && crate::path::has_extension(&folder, "bin") {
-				match GMAFile::open(&folder) {
-					Ok(gma) => gma,
-					Err(_) => {
+				match GMAFile::peek_format(&folder) {
+					Ok(GmaFormat::Lzma) => {
 						transaction.status("decompressing");
-						match GMAFile::decompress(folder, transaction.clone()) {
+						match GMAFile::decompress(folder, crate::gma::DEFAULT_LZMA_MEMLIMIT, transaction.clone()) {
 							Ok(gma) => {
 								transaction.progress_reset();
 								gma
@@ -125,12 +124,17 @@ impl Downloads {
 							Err(err) => return transaction.error(err.to_string(), turbonone!()),
 						}
 					}
+					_ => match GMAFile::open(&folder) {
+						Ok(gma) => gma,
+						Err(err) => return transaction.error(err.to_string(), turbonone!()),
+					},
 				}
 			} else {
 				return transaction.error("ERR_DOWNLOAD_MISSING", turbonone!());
 			};
 
-			gma.id = Some(item);
+			// Before extracting, so the extracted folder gets the ID in its name
+			gma.set_ws_id(item);
 
 			transaction.status("reading_metadata");
 			transaction.data((Some(gma.metadata.as_ref().map(|metadata| metadata.title().to_owned())), gma.size));
@@ -172,9 +176,16 @@ impl Downloads {
 		}
 	}
 
+	/// Downloads and extracts the items to the destination in the user's settings
 	pub fn download<IDs: Into<IDList>>(&self, ids: IDs) {
+		self.download_to(ids, app_data!().settings.read().extract_destination.to_owned());
+	}
+
+	/// Downloads the items (or the items in a collection), decompresses them if needed and extracts them to `extract_destination`.
+	/// Each download and extraction reports its progress through its own transaction, announced by the `DownloadStarted` and `ExtractionStarted` events
+	pub fn download_to<IDs: Into<IDList>>(&self, ids: IDs, extract_destination: ExtractDestination) {
 		let mut ids: Vec<PublishedFileId> = ids.into().into();
-		let extract_destination = Arc::new(app_data!().settings.read().extract_destination.to_owned());
+		let extract_destination = Arc::new(extract_destination);
 		let possible_collections: Vec<PublishedFileId> = {
 			if let Some(workshop) = steam!().workshop.try_read_for(std::time::Duration::from_millis(51)) {
 				let workshop_cache = &workshop.0;
@@ -360,3 +371,11 @@ impl Downloads {
 pub fn workshop_download(ids: Vec<PublishedFileId>) {
 	downloads!().download(ids);
 }
+
+#[tauri::command]
+pub fn extract_workshop_item(id: PublishedFileId, dest: Option<ExtractDestination>) {
+	match dest {
+		Some(dest) => downloads!().download_to(id, dest),
+		None => downloads!().download(id),
+	}
+}
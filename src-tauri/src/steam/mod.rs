@@ -160,6 +160,11 @@ impl Steam {
 		self.connected.load(std::sync::atomic::Ordering::Acquire)
 	}
 
+	/// Whether the Steam client is initialized and connected, so calls into it will actually get their callbacks
+	pub fn is_ready(&self) -> bool {
+		self.connected() && self.interface.try_borrow().map_or(false, |interface| interface.is_some())
+	}
+
 	fn set_connected(&self, connected: bool) {
 		self.connected.store(connected, std::sync::atomic::Ordering::Release);
 		webview_emit!(if connected { "SteamConnected" } else { "SteamDisconnected" });
@@ -180,6 +185,18 @@ impl Steam {
 		}
 	}
 
+	/// Like `client_wait`, but gives up with `PublishError::SteamNotRunning` if Steam isn't ready within `timeout`
+	pub fn wait_ready(&self, timeout: std::time::Duration) -> Result<(), publishing::PublishError> {
+		let started = std::time::Instant::now();
+		while !self.is_ready() {
+			if started.elapsed() >= timeout {
+				return Err(publishing::PublishError::SteamNotRunning);
+			}
+			sleep_ms!(50);
+		}
+		Ok(())
+	}
+
 	// Callbacks //
 	pub fn callback_once_with_data<C: 'static, EqF>(&'static self, eq_f: EqF, timeout: u8) -> Option<C>
 	where
@@ -269,6 +286,22 @@ impl Steam {
 		self.client().single.run_callbacks();
 		sleep_ms!(50);
 	}
+
+	/// Pumps callbacks every 50ms on the calling thread until `poll` returns `Some`, or Steam goes away
+	pub fn run_callbacks_until<T, F>(&self, mut poll: F) -> Result<T, publishing::PublishError>
+	where
+		F: FnMut() -> Option<T>,
+	{
+		loop {
+			if let Some(result) = poll() {
+				return Ok(result);
+			}
+			if !self.is_ready() {
+				return Err(publishing::PublishError::SteamNotRunning);
+			}
+			self.run_callbacks();
+		}
+	}
 }
 
 #[tauri::command]
@@ -1,50 +1,123 @@
 use crate::{
-	gma::{GMAEntry, GMAFile, GMAFilePointers, GMAMetadata},
+	gma::{GMAEntry, GMAError, GMAFile, GMAMetadata, TreeNode},
 	Transaction, GMOD_APP_ID,
 };
 use image::{DynamicImage, GenericImageView, ImageError, ImageFormat};
 use parking_lot::Mutex;
 use path_slash::PathBufExt;
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 use std::{
+	collections::{hash_map::Entry, HashMap},
 	fs::File,
 	io::BufReader,
-	mem::MaybeUninit,
 	path::{Path, PathBuf},
 	sync::Arc,
 };
-use steamworks::{PublishedFileId, SteamError};
+use steamworks::{ClientManager, PublishedFileId, PublishedFileVisibility, SteamError, UpdateWatchHandle};
 use walkdir::WalkDir;
 
-#[cfg(not(target_os = "windows"))]
-use std::collections::HashSet;
-
 pub enum PublishError {
 	NotWhitelisted(Vec<String>),
 	NoEntries,
-	DuplicateEntry(String),
+	/// Paths that are the same once lowercased, which is how Garry's Mod sees them. In pairs, as they were cased on disk
+	DuplicateEntries(Vec<String>),
+	InvalidPath(PathBuf),
 	InvalidContentPath,
+	/// The title is empty, longer than `WORKSHOP_TITLE_MAX_LEN` bytes or contains control characters
+	InvalidTitle(String),
 	MultipleGMAs,
-	IconTooLarge,
+	/// The GMA has legacy metadata or an empty type, and the Workshop won't take an addon without one
+	MissingAddonType,
+	/// A metadata-only update gave an addon type but no tags. Steam replaces all of the tags at once, and the type is one of them
+	AddonTypeWithoutTags,
+	/// A .gma file was found in a subfolder of the content path, which is almost always a leftover from an old build
+	NestedGMA(PathBuf),
+	/// The size in bytes of the image, or of the smallest re-encoding we managed
+	IconTooLarge(u64),
 	IconTooSmall,
 	IconInvalidFormat,
+	IconAnimated,
+	/// The preview image was moved or deleted after it was selected
+	PreviewNotFound(PathBuf),
+	Cancelled,
 	IOError,
+	/// Steam isn't running (or isn't connected), so nothing would ever answer us
+	SteamNotRunning,
+	/// The item was published, but won't be visible until the user accepts the Steam Workshop legal agreement
+	LegalAgreementRequired(PublishedFileId),
 	SteamError(SteamError),
 	ImageError(ImageError),
+	GMAError(GMAError),
 }
 impl std::fmt::Display for PublishError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			PublishError::NotWhitelisted(whitelisted) => write!(f, "ERR_WHITELIST:{}", whitelisted.join("\n")),
 			PublishError::NoEntries => write!(f, "ERR_NO_ENTRIES"),
-			PublishError::DuplicateEntry(path) => write!(f, "ERR_DUPLICATE_ENTRIES:{}", path),
+			PublishError::DuplicateEntries(paths) => write!(f, "ERR_DUPLICATE_ENTRIES:{}", paths.join("\n")),
+			PublishError::InvalidPath(path) => write!(f, "ERR_INVALID_PATH:{}", path.display()),
 			PublishError::InvalidContentPath => write!(f, "ERR_INVALID_CONTENT_PATH"),
+			PublishError::InvalidTitle(title) => write!(f, "ERR_INVALID_TITLE:{}", title),
 			PublishError::MultipleGMAs => write!(f, "ERR_MULTIPLE_GMAS"),
-			PublishError::IconTooLarge => write!(f, "ERR_ICON_TOO_LARGE"),
+			PublishError::MissingAddonType => write!(f, "ERR_MISSING_ADDON_TYPE"),
+			PublishError::AddonTypeWithoutTags => write!(f, "ERR_ADDON_TYPE_WITHOUT_TAGS"),
+			PublishError::NestedGMA(path) => write!(f, "ERR_NESTED_GMA:{}", path.display()),
+			PublishError::IconTooLarge(_) => write!(f, "ERR_ICON_TOO_LARGE"),
 			PublishError::IconTooSmall => write!(f, "ERR_ICON_TOO_SMALL"),
 			PublishError::IconInvalidFormat => write!(f, "ERR_ICON_INVALID_FORMAT"),
+			PublishError::IconAnimated => write!(f, "ERR_ICON_ANIMATED"),
+			PublishError::PreviewNotFound(path) => write!(f, "ERR_PREVIEW_NOT_FOUND:{}", path.display()),
+			PublishError::Cancelled => write!(f, "ERR_CANCELLED"),
 			PublishError::IOError => write!(f, "ERR_IO_ERROR"),
+			PublishError::SteamNotRunning => write!(f, "ERR_STEAM_NOT_RUNNING"),
+			PublishError::LegalAgreementRequired(id) => write!(f, "ERR_WORKSHOP_LEGAL_AGREEMENT:{}", id.0),
 			PublishError::SteamError(error) => write!(f, "ERR_STEAM_ERROR:{}", error),
 			PublishError::ImageError(error) => write!(f, "ERR_IMAGE_ERROR:{}", error),
+			PublishError::GMAError(error) => write!(f, "{}", error),
+		}
+	}
+}
+impl PublishError {
+	/// The error code, which the frontend translates
+	pub fn code(&self) -> &'static str {
+		match self {
+			PublishError::NotWhitelisted(_) => "ERR_WHITELIST",
+			PublishError::NoEntries => "ERR_NO_ENTRIES",
+			PublishError::DuplicateEntries(_) => "ERR_DUPLICATE_ENTRIES",
+			PublishError::InvalidPath(_) => "ERR_INVALID_PATH",
+			PublishError::InvalidContentPath => "ERR_INVALID_CONTENT_PATH",
+			PublishError::InvalidTitle(_) => "ERR_INVALID_TITLE",
+			PublishError::MultipleGMAs => "ERR_MULTIPLE_GMAS",
+			PublishError::MissingAddonType => "ERR_MISSING_ADDON_TYPE",
+			PublishError::AddonTypeWithoutTags => "ERR_ADDON_TYPE_WITHOUT_TAGS",
+			PublishError::NestedGMA(_) => "ERR_NESTED_GMA",
+			PublishError::IconTooLarge(_) => "ERR_ICON_TOO_LARGE",
+			PublishError::IconTooSmall => "ERR_ICON_TOO_SMALL",
+			PublishError::IconInvalidFormat => "ERR_ICON_INVALID_FORMAT",
+			PublishError::IconAnimated => "ERR_ICON_ANIMATED",
+			PublishError::PreviewNotFound(_) => "ERR_PREVIEW_NOT_FOUND",
+			PublishError::Cancelled => "ERR_CANCELLED",
+			PublishError::IOError => "ERR_IO_ERROR",
+			PublishError::SteamNotRunning => "ERR_STEAM_NOT_RUNNING",
+			PublishError::LegalAgreementRequired(_) => "ERR_WORKSHOP_LEGAL_AGREEMENT",
+			PublishError::SteamError(_) => "ERR_STEAM_ERROR",
+			PublishError::ImageError(_) => "ERR_IMAGE_ERROR",
+			PublishError::GMAError(error) => error.code(),
+		}
+	}
+
+	/// Whatever details the error carries, e.g. the paths that aren't whitelisted. `null` if there are none
+	pub fn data(&self) -> serde_json::Value {
+		match self {
+			PublishError::NotWhitelisted(paths) | PublishError::DuplicateEntries(paths) => json!(paths),
+			PublishError::InvalidTitle(title) => json!(title),
+			PublishError::InvalidPath(path) | PublishError::NestedGMA(path) | PublishError::PreviewNotFound(path) => json!(path),
+			PublishError::IconTooLarge(size) => json!(size),
+			PublishError::LegalAgreementRequired(id) => json!(id.0),
+			PublishError::SteamError(error) => json!(error.to_string()),
+			PublishError::ImageError(error) => json!(error.to_string()),
+			PublishError::GMAError(error) => error.data(),
+			_ => serde_json::Value::Null,
 		}
 	}
 }
@@ -53,7 +126,12 @@ impl serde::Serialize for PublishError {
 	where
 		S: serde::Serializer,
 	{
-		serializer.serialize_str(&self.to_string())
+		use serde::ser::SerializeStruct;
+
+		let mut error = serializer.serialize_struct("PublishError", 2)?;
+		error.serialize_field("code", self.code())?;
+		error.serialize_field("data", &self.data())?;
+		error.end()
 	}
 }
 impl From<SteamError> for PublishError {
@@ -66,6 +144,11 @@ impl From<ImageError> for PublishError {
 		PublishError::ImageError(error)
 	}
 }
+impl From<GMAError> for PublishError {
+	fn from(error: GMAError) -> PublishError {
+		PublishError::GMAError(error)
+	}
+}
 impl From<std::io::Error> for PublishError {
 	fn from(_: std::io::Error) -> PublishError {
 		PublishError::IOError
@@ -85,37 +168,61 @@ impl From<ContentPath> for PathBuf {
 		val.0
 	}
 }
+/// How many folders deep `ContentPath::new` looks for stray .gma files
+const NESTED_GMA_MAX_DEPTH: usize = 8;
+
 impl ContentPath {
+	/// Accepts a directory containing exactly one .gma file and none in its subfolders. Use `ContentPath::stage` for a lone .gma file
 	pub fn new(path: PathBuf) -> Result<ContentPath, PublishError> {
 		if !path.is_dir() {
 			return Err(PublishError::InvalidContentPath);
 		}
 
-		let mut gma_path: MaybeUninit<PathBuf> = MaybeUninit::uninit();
-		for (i, path) in path
-			.read_dir()?
-			.filter_map(|entry| {
-				entry.ok().and_then(|entry| {
-					let path = entry.path();
-					let extension = path.extension()?;
-					if extension == "gma" {
-						Some(path)
-					} else {
-						None
-					}
-				})
+		let mut gma_path = None;
+		for path in path.read_dir()?.filter_map(|entry| {
+			entry.ok().and_then(|entry| {
+				let path = entry.path();
+				let extension = path.extension()?;
+				if extension == "gma" {
+					Some(path)
+				} else {
+					None
+				}
 			})
-			.enumerate()
-		{
-			if i > 0 {
+		}) {
+			if gma_path.is_some() {
 				return Err(PublishError::MultipleGMAs);
 			}
-			unsafe {
-				gma_path.as_mut_ptr().write(path);
-			}
+			gma_path = Some(path);
+		}
+
+		if let Some(nested) = WalkDir::new(&path)
+			.min_depth(2)
+			.max_depth(NESTED_GMA_MAX_DEPTH)
+			.into_iter()
+			.filter_map(|entry| entry.ok())
+			.find(|entry| entry.file_type().is_file() && entry.path().extension().map_or(false, |extension| extension.eq_ignore_ascii_case("gma")))
+		{
+			return Err(PublishError::NestedGMA(nested.into_path()));
 		}
 
-		Ok(ContentPath(unsafe { gma_path.assume_init() }))
+		gma_path.map(ContentPath).ok_or(PublishError::InvalidContentPath)
+	}
+
+	/// Accepts a packed .gma file by hardlinking (or copying, if that fails) it into a publishing folder of its own for this transaction,
+	/// as Steam can only upload a folder. Remove it with `remove_publishing_dir` once the upload is done
+	pub fn stage(gma_path: &Path, transaction_id: u32) -> Result<ContentPath, PublishError> {
+		if !gma_path.is_file() || !gma_path.extension().map_or(false, |extension| extension.eq_ignore_ascii_case("gma")) {
+			return Err(PublishError::InvalidContentPath);
+		}
+
+		let mut staged = publishing_gma_path(transaction_id)?;
+		if std::fs::hard_link(gma_path, &staged).is_err() {
+			std::fs::copy(gma_path, &staged)?;
+		}
+
+		staged.pop();
+		ContentPath::new(staged)
 	}
 }
 
@@ -134,6 +241,9 @@ pub enum WorkshopIcon {
 	},
 	Default,
 }
+/// The size Steam recommends for Workshop previews
+const WORKSHOP_ICON_RECOMMENDED_SIZE: u32 = 512;
+
 impl WorkshopIcon {
 	pub fn can_upscale(width: u32, height: u32, format: ImageFormat) -> bool {
 		!matches!(format, ImageFormat::Gif) && ((width < 512 || height < 512) || (width != height))
@@ -184,9 +294,13 @@ impl WorkshopIcon {
 	pub fn new<P: AsRef<Path>>(path: P, upscale: bool) -> Result<WorkshopIcon, PublishError> {
 		let path = path.as_ref();
 
-		let len = path.metadata()?.len();
+		let len = match path.metadata() {
+			Ok(metadata) => metadata.len(),
+			Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Err(PublishError::PreviewNotFound(path.to_path_buf())),
+			Err(error) => return Err(error.into()),
+		};
 		if len > WORKSHOP_ICON_MAX_SIZE {
-			return Err(PublishError::IconTooLarge);
+			return Err(PublishError::IconTooLarge(len));
 		} else if len < WORKSHOP_ICON_MIN_SIZE {
 			return Err(PublishError::IconTooSmall);
 		}
@@ -200,6 +314,39 @@ impl WorkshopIcon {
 		};
 
 		let image = image::load(BufReader::new(File::open(path)?), image_format)?;
+
+		// Workshop previews shouldn't be animated, so either reject them or just use the first frame
+		if image_format == ImageFormat::Gif {
+			use image::{codecs::gif::GifDecoder, AnimationDecoder};
+
+			let frames = GifDecoder::new(BufReader::new(File::open(path)?))?.into_frames().take(2).count();
+			if frames > 1 {
+				if app_data!().settings.read().reject_animated_icons {
+					return Err(PublishError::IconAnimated);
+				}
+
+				let mut flattened = app_data!().temp_dir().to_owned();
+				flattened.push("gmpublisher_flattened_icon.png");
+
+				// `image::load` already decoded just the first frame
+				image.save_with_format(&flattened, ImageFormat::Png)?;
+
+				let flattened_len = flattened.metadata()?.len();
+				if flattened_len > WORKSHOP_ICON_MAX_SIZE {
+					return Err(PublishError::IconTooLarge(flattened_len));
+				}
+
+				return Ok(WorkshopIcon::Custom {
+					path: flattened,
+					width: image.width(),
+					height: image.height(),
+					format: ImageFormat::Png,
+					upscale,
+					image,
+				});
+			}
+		}
+
 		Ok(WorkshopIcon::Custom {
 			path: path.to_path_buf(),
 			width: image.width(),
@@ -209,6 +356,63 @@ impl WorkshopIcon {
 			image,
 		})
 	}
+
+	/// Re-encodes any image `new` accepts as a JPEG that fits within Steam's recommended 512x512, keeping its aspect ratio.
+	/// The quality is lowered until it's under the size limit, so unlike `new`, large images aren't rejected
+	pub fn normalize<P: AsRef<Path>>(path: P) -> Result<WorkshopIcon, PublishError> {
+		let path = path.as_ref();
+
+		if !path.is_file() {
+			return Err(PublishError::PreviewNotFound(path.to_path_buf()));
+		}
+
+		let image_format = match path.extension().and_then(|x| x.to_str()).unwrap_or("jpg").to_ascii_lowercase().as_str() {
+			"png" => ImageFormat::Png,
+			"gif" => ImageFormat::Gif,
+			"jpeg" | "jpg" => ImageFormat::Jpeg,
+			_ => return Err(PublishError::IconInvalidFormat),
+		};
+
+		// For animated GIFs this is just the first frame
+		let image = image::load(BufReader::new(File::open(path)?), image_format)?.resize(
+			WORKSHOP_ICON_RECOMMENDED_SIZE,
+			WORKSHOP_ICON_RECOMMENDED_SIZE,
+			image::imageops::FilterType::CatmullRom,
+		);
+
+		// JPEGs can't be transparent
+		let rgb = image.to_rgb8();
+
+		let mut normalized = app_data!().temp_dir().to_owned();
+		normalized.push("gmpublisher_normalized_icon.jpg");
+
+		let mut smallest = 0;
+		for quality in (30..=90).rev().step_by(10) {
+			let mut jpeg = Vec::new();
+			image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality).encode(
+				rgb.as_raw(),
+				rgb.width(),
+				rgb.height(),
+				image::ColorType::Rgb8,
+			)?;
+
+			smallest = jpeg.len() as u64;
+			if smallest <= WORKSHOP_ICON_MAX_SIZE {
+				std::fs::write(&normalized, jpeg)?;
+
+				return Ok(WorkshopIcon::Custom {
+					path: normalized,
+					width: rgb.width(),
+					height: rgb.height(),
+					format: ImageFormat::Jpeg,
+					upscale: false,
+					image: DynamicImage::ImageRgb8(rgb),
+				});
+			}
+		}
+
+		Err(PublishError::IconTooLarge(smallest))
+	}
 }
 
 pub enum WorkshopUpdateType {
@@ -227,12 +431,51 @@ pub enum WorkshopUpdateType {
 		preview: Option<WorkshopIcon>,
 		changes: Option<String>,
 	},
+	/// Changes the item's details without uploading any content, so the content doesn't need to be available locally.
+	/// Steam replaces all of the tags at once, so `addon_type` can only be given alongside `tags`, or it fails with `PublishError::AddonTypeWithoutTags`
+	WorkshopMetadataUpdate {
+		title: Option<String>,
+		description: Option<String>,
+		tags: Option<Vec<String>>,
+		addon_type: Option<String>,
+		visibility: Option<PublishedFileVisibility>,
+		changes: Option<String>,
+	},
+}
+
+type UpdateResult = Arc<Mutex<Option<Result<(PublishedFileId, bool), SteamError>>>>;
+
+/// Steam's limit on titles, in bytes of UTF-8. Longer titles are silently truncated
+pub const WORKSHOP_TITLE_MAX_LEN: usize = 128;
+
+/// Rejects titles Steam would truncate or mangle, rather than finding out after the upload
+pub fn validate_title(title: &str) -> Result<(), PublishError> {
+	if title.trim().is_empty() || title.len() > WORKSHOP_TITLE_MAX_LEN || title.chars().any(char::is_control) {
+		Err(PublishError::InvalidTitle(title.to_owned()))
+	} else {
+		Ok(())
+	}
 }
 
 impl Steam {
 	pub fn update(&self, id: PublishedFileId, details: WorkshopUpdateType, transaction: &Transaction) -> Result<bool, PublishError> {
 		use WorkshopUpdateType::*;
 
+		if !self.is_ready() {
+			return Err(PublishError::SteamNotRunning);
+		}
+
+		match &details {
+			Creation { title, .. } | Update { title, .. } => validate_title(title)?,
+			WorkshopMetadataUpdate {
+				addon_type: Some(_),
+				tags: None,
+				..
+			} => return Err(PublishError::AddonTypeWithoutTags),
+			WorkshopMetadataUpdate { title: Some(title), .. } => validate_title(title)?,
+			WorkshopMetadataUpdate { title: None, .. } => {}
+		}
+
 		let result = Arc::new(Mutex::new(None));
 		let result_ref = result.clone();
 		let update_handle = match details {
@@ -287,48 +530,88 @@ impl Steam {
 					*result_ref.lock() = Some(result);
 				})
 			}
-		};
 
-		let mut last_processed;
-		let result = loop {
-			let (processed, progress, total) = update_handle.progress();
-			last_processed = processed;
-			if !matches!(processed, steamworks::UpdateStatus::Invalid) {
-				transaction.status(match processed {
-					steamworks::UpdateStatus::Invalid => unreachable!(),
-					steamworks::UpdateStatus::PreparingConfig => "PUBLISH_PREPARING_CONFIG",
-					steamworks::UpdateStatus::PreparingContent => "PUBLISH_PREPARING_CONTENT",
-					steamworks::UpdateStatus::UploadingContent => "PUBLISH_UPLOADING_CONTENT",
-					steamworks::UpdateStatus::UploadingPreviewFile => "PUBLISH_UPLOADING_PREVIEW_FILE",
-					steamworks::UpdateStatus::CommittingChanges => "PUBLISH_COMMITTING_CHANGES",
-				});
-			}
-			if total == 0 || last_processed != processed {
-				transaction.progress_reset();
-			} else {
-				transaction.data(total);
-				transaction.progress(progress as f64 / total as f64);
-			}
+			WorkshopMetadataUpdate {
+				title,
+				description,
+				tags,
+				addon_type,
+				visibility,
+				changes,
+			} => {
+				let mut update = self.client().ugc().start_item_update(GMOD_APP_ID, id);
 
-			if !result.is_locked() && result.lock().is_some() {
-				break Arc::try_unwrap(result).unwrap().into_inner().unwrap();
-			} else {
-				self.run_callbacks();
+				if let Some(title) = title {
+					update = update.title(&title);
+				}
+
+				if let Some(description) = description {
+					update = update.description(&description);
+				}
+
+				if let Some(mut tags) = tags {
+					tags.reserve(tags.len() + 2);
+					tags.push("Addon".to_string());
+					if let Some(addon_type) = addon_type {
+						tags.push(addon_type);
+					}
+					update = update.tags(tags, false);
+				}
+
+				if let Some(visibility) = visibility {
+					update = update.visibility(visibility);
+				}
+
+				update.submit(changes.as_deref(), move |result| {
+					*result_ref.lock() = Some(result);
+				})
 			}
 		};
 
-		match result {
-			Ok((_, legal_agreement)) => {
-				transaction.progress(1.);
-				Ok(legal_agreement)
-			}
-			Err(error) => Err(PublishError::SteamError(error)),
-		}
+		self.watch_update(update_handle, result, transaction)
+	}
+
+	/// Pushes the title, description, tags and visibility to an existing item in one metadata-only update, leaving its content alone.
+	/// The push counterpart to `fetch_workshop_items`. Returns whether the user needs to accept the Workshop legal agreement
+	#[allow(clippy::too_many_arguments)]
+	pub fn sync_metadata(
+		&self,
+		id: PublishedFileId,
+		title: String,
+		description: String,
+		tags: Vec<String>,
+		addon_type: String,
+		visibility: PublishedFileVisibility,
+		transaction: &Transaction,
+	) -> Result<bool, PublishError> {
+		self.update(
+			id,
+			WorkshopUpdateType::WorkshopMetadataUpdate {
+				title: Some(title),
+				description: Some(description),
+				tags: Some(tags),
+				addon_type: Some(addon_type),
+				visibility: Some(visibility),
+				changes: None,
+			},
+			transaction,
+		)
 	}
 
 	pub fn publish(&self, details: WorkshopUpdateType, transaction: &Transaction) -> (Option<PublishedFileId>, Result<bool, PublishError>) {
 		debug_assert!(matches!(details, WorkshopUpdateType::Creation { .. }));
 
+		if !self.is_ready() {
+			return (None, Err(PublishError::SteamNotRunning));
+		}
+
+		// Checked again by `update`, but there's no point creating an item we can't upload to
+		if let WorkshopUpdateType::Creation { ref title, .. } = details {
+			if let Err(error) = validate_title(title) {
+				return (None, Err(error));
+			}
+		}
+
 		let published = Arc::new(Mutex::new(None));
 		let published_ref = published.clone();
 		self.client()
@@ -337,21 +620,131 @@ impl Steam {
 				*published_ref.lock() = Some(result);
 			});
 
-		loop {
-			if let Some(published_ref) = published.try_lock() {
-				if published_ref.is_some() {
-					break;
-				}
+		let (id, legal_agreement) = match self
+			.run_callbacks_until(|| published.try_lock().and_then(|mut published| published.take()))
+			.and_then(|created| created.map_err(PublishError::SteamError))
+		{
+			Ok(created) => created,
+			Err(error) => return (None, Err(error)),
+		};
+
+		(
+			Some(id),
+			self.update(id, details, transaction)
+				.map(|update_legal_agreement| legal_agreement || update_legal_agreement),
+		)
+	}
+
+	/// Publishes a GMA that's already packed as a new item, with the type and tags from its metadata.
+	/// The GMA is hardlinked (or copied, if that fails) into the publishing content folder, which is cleaned up afterwards
+	pub fn publish_gma(
+		&self,
+		gma: &GMAFile,
+		title: String,
+		preview: WorkshopIcon,
+		transaction: &Transaction,
+	) -> (Option<PublishedFileId>, Result<bool, PublishError>) {
+		let metadata = match gma.metadata.clone() {
+			Some(metadata) => metadata,
+			None => match GMAFile::open_metadata_only(&gma.path) {
+				Ok(opened) => opened.metadata.unwrap(),
+				Err(error) => return (None, Err(error.into())),
+			},
+		};
+
+		// Legacy GMAs don't have a type, and the Workshop won't take an addon without one
+		let (addon_type, tags) = match metadata {
+			GMAMetadata::Standard { addon_type, tags, .. } if !addon_type.is_empty() => (addon_type, tags),
+			_ => return (None, Err(PublishError::MissingAddonType)),
+		};
+
+		let copied = (|| -> Result<ContentPath, PublishError> {
+			if gma.membuffer.is_none() && gma.reader_source.is_none() {
+				return ContentPath::stage(&gma.path, transaction.id);
 			}
-			self.run_callbacks();
-		}
 
-		let id = match Arc::try_unwrap(published).unwrap().into_inner().unwrap() {
-			Ok((id, _)) => id,
-			Err(error) => return (None, Err(PublishError::SteamError(error))),
+			let mut path = publishing_gma_path(transaction.id)?;
+			std::io::copy(&mut *gma.read()?, &mut File::create(&path)?)?;
+
+			path.pop();
+			ContentPath::new(path)
+		})();
+
+		let result = match copied {
+			Ok(content_path) => self.publish(
+				WorkshopUpdateType::Creation {
+					title,
+					path: content_path,
+					tags,
+					addon_type,
+					preview,
+				},
+				transaction,
+			),
+			Err(error) => (None, Err(error)),
 		};
 
-		(Some(id), self.update(id, details, transaction))
+		remove_publishing_dir(transaction.id);
+
+		result
+	}
+
+	/// Creates a collection containing `items`. Steam stores a collection's items as its dependencies, so they're added one by one.
+	/// Returns the collection's ID and whether the user needs to accept the Workshop legal agreement
+	pub fn create_collection(
+		&self,
+		title: String,
+		items: Vec<PublishedFileId>,
+		transaction: &Transaction,
+	) -> Result<(PublishedFileId, bool), PublishError> {
+		if !self.is_ready() {
+			return Err(PublishError::SteamNotRunning);
+		}
+
+		let created = Arc::new(Mutex::new(None));
+		let created_ref = created.clone();
+		self.client()
+			.ugc()
+			.create_item(GMOD_APP_ID, steamworks::FileType::Collection, move |result| {
+				*created_ref.lock() = Some(result);
+			});
+
+		let id = self.run_callbacks_until(|| created.try_lock().and_then(|mut created| created.take()))??.0;
+
+		let result = Arc::new(Mutex::new(None));
+		let result_ref = result.clone();
+		let update_handle = self
+			.client()
+			.ugc()
+			.start_item_update(GMOD_APP_ID, id)
+			.title(&title)
+			.description("Created with [url=https://github.com/WilliamVenner/gmpublisher]gmpublisher[/url]")
+			.submit(None, move |result| {
+				*result_ref.lock() = Some(result);
+			});
+
+		let legal_agreement = self.watch_update(update_handle, result, transaction)?;
+
+		transaction.progress_reset();
+
+		let items_len_f = items.len() as f64;
+		for (i, item) in items.into_iter().enumerate() {
+			if transaction.aborted() {
+				return Err(PublishError::Cancelled);
+			}
+
+			let added = Arc::new(Mutex::new(None));
+			let added_ref = added.clone();
+			self.client().ugc().add_dependency(id, item, move |result| {
+				*added_ref.lock() = Some(result);
+			});
+
+			self.run_callbacks_until(|| added.try_lock().and_then(|mut added| added.take()))??;
+
+			transaction.progress(((i + 1) as f64) / items_len_f);
+		}
+
+		Ok((id, legal_agreement))
 	}
 
 	pub fn update_icon(&self, addon_id: PublishedFileId, icon: WorkshopIcon, transaction: &Transaction) -> Result<bool, PublishError> {
@@ -366,10 +759,17 @@ impl Steam {
 				*result_ref.lock() = Some(result);
 			});
 
-		let mut last_processed;
-		let result = loop {
+		self.watch_update(update_handle, result, transaction)
+	}
+
+	fn watch_update(
+		&self,
+		update_handle: UpdateWatchHandle<ClientManager>,
+		result: UpdateResult,
+		transaction: &Transaction,
+	) -> Result<bool, PublishError> {
+		let result = self.run_callbacks_until(|| {
 			let (processed, progress, total) = update_handle.progress();
-			last_processed = processed;
 			if !matches!(processed, steamworks::UpdateStatus::Invalid) {
 				transaction.status(match processed {
 					steamworks::UpdateStatus::Invalid => unreachable!(),
@@ -380,21 +780,21 @@ impl Steam {
 					steamworks::UpdateStatus::CommittingChanges => "PUBLISH_COMMITTING_CHANGES",
 				});
 			}
-			if total == 0 || last_processed != processed {
+			if total == 0 {
 				transaction.progress_reset();
 			} else {
 				transaction.data(total);
 				transaction.progress(progress as f64 / total as f64);
 			}
 
-			if !result.is_locked() && result.lock().is_some() {
-				break Arc::try_unwrap(result).unwrap().into_inner().unwrap();
+			if !result.is_locked() {
+				result.lock().take()
 			} else {
-				self.run_callbacks();
+				None
 			}
-		};
+		});
 
-		match result {
+		match result? {
 			Ok((_, legal_agreement)) => {
 				transaction.progress(1.);
 				Ok(legal_agreement)
@@ -404,8 +804,30 @@ impl Steam {
 	}
 }
 
+/// Finishes with the entries and their total size. With `as_tree`, the entries come as a `TreeNode` like `preview_entry_tree`'s instead of a flat list
 #[tauri::command]
-pub fn verify_whitelist(path: PathBuf) -> Result<(Vec<GMAEntry>, u64), PublishError> {
+pub fn verify_whitelist(path: PathBuf, compute_crcs: Option<bool>, as_tree: Option<bool>) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || match scan_content(&path, compute_crcs.unwrap_or(false), &transaction) {
+		Ok((entries, size)) if as_tree.unwrap_or(false) => transaction.finished((TreeNode::from_entries(&entries), size)),
+		Ok(result) => transaction.finished(result),
+		Err(PublishError::Cancelled) => {}
+		Err(error) => transaction.error(error.code(), error.data()),
+	});
+
+	id
+}
+
+/// Walks the content directory, checking everything against the whitelist.
+///
+/// The total number of files isn't known upfront, so the number of files scanned so far is sent as transaction data.
+///
+/// CRCs are left as 0 unless `compute_crcs` is set, as hashing everything makes the scan a lot slower.
+///
+/// Cancelling the transaction stops the walk early with `PublishError::Cancelled`.
+pub fn scan_content(path: &Path, compute_crcs: bool, transaction: &Transaction) -> Result<(Vec<GMAEntry>, u64), PublishError> {
 	if !path.is_dir() || !path.is_absolute() {
 		return Err(PublishError::InvalidContentPath);
 	}
@@ -428,14 +850,20 @@ pub fn verify_whitelist(path: PathBuf) -> Result<(Vec<GMAEntry>, u64), PublishEr
 	let mut failed_extra = false;
 	let mut failed = Vec::with_capacity(10);
 	let mut files = Vec::new();
+	let mut paths = Vec::new();
+
+	// Lowercased path -> the path as it's cased on disk
+	let mut dedup: HashMap<String, String> = HashMap::new();
+	let mut duplicates = Vec::new();
 
-	#[cfg(not(target_os = "windows"))]
-	let mut dedup: HashSet<String> = HashSet::new();
+	let mut scanned: usize = 0;
 
-	for (path, relative_path) in WalkDir::new(&path)
+	let root_path = path;
+	for (path, original_path, relative_path) in WalkDir::new(path)
 		.follow_links(true)
 		.contents_first(true)
 		.into_iter()
+		.take_while(|_| !transaction.aborted())
 		.filter_map(|entry| {
 			let path = match entry {
 				Ok(entry) => entry.into_path(),
@@ -449,23 +877,38 @@ pub fn verify_whitelist(path: PathBuf) -> Result<(Vec<GMAEntry>, u64), PublishEr
 				return None;
 			}
 
-			let relative_path = {
-				let mut relative_path = path.to_slash_lossy();
-				if relative_path.len() < root_path_strip_len {
+			scanned += 1;
+			if scanned % 100 == 0 {
+				transaction.data(scanned);
+			}
+
+			let original_path = {
+				let mut original_path = path.to_slash_lossy();
+				if original_path.len() < root_path_strip_len {
 					return None;
 				}
-				relative_path.split_off(root_path_strip_len).to_lowercase()
+				original_path.split_off(root_path_strip_len).replace('\\', "/")
 			};
+			let relative_path = original_path.to_lowercase();
 
-			Some((path, relative_path))
+			Some((path, original_path, relative_path))
 		})
-		.filter(|(_, relative_path)| crate::gma::whitelist::filter_default_ignored(relative_path))
-		.filter(|(_, relative_path)| !crate::gma::whitelist::is_ignored(relative_path, &ignore))
+		.filter(|(_, _, relative_path)| crate::gma::whitelist::filter_default_ignored(relative_path))
+		.filter(|(_, _, relative_path)| !crate::gma::whitelist::is_ignored(relative_path, &ignore))
 	{
-		#[cfg(not(target_os = "windows"))]
-		{
-			if !dedup.insert(relative_path.to_owned()) {
-				return Err(PublishError::DuplicateEntry(relative_path));
+		// Entry names are stored as UTF-8 in the GMA, so anything else can't be packed faithfully
+		if path.strip_prefix(root_path).map_or(false, |relative| relative.to_str().is_none()) {
+			return Err(PublishError::InvalidPath(path));
+		}
+
+		match dedup.entry(relative_path.clone()) {
+			Entry::Occupied(existing) => {
+				duplicates.push(existing.get().to_owned());
+				duplicates.push(original_path);
+				continue;
+			}
+			Entry::Vacant(vacant) => {
+				vacant.insert(original_path);
 			}
 		}
 
@@ -484,10 +927,37 @@ pub fn verify_whitelist(path: PathBuf) -> Result<(Vec<GMAEntry>, u64), PublishEr
 				size: entry_size,
 				crc: 0,
 				index: 0,
+				compressed: false,
 			});
+			if compute_crcs {
+				paths.push(path);
+			}
 		}
 	}
 
+	if transaction.aborted() {
+		return Err(PublishError::Cancelled);
+	}
+
+	if compute_crcs && failed.is_empty() {
+		files.par_iter_mut().zip(paths.into_par_iter()).for_each(|(entry, path)| {
+			if transaction.aborted() {
+				return;
+			}
+			if let Ok(contents) = std::fs::read(path) {
+				entry.crc = crc32fast::hash(&contents);
+			}
+		});
+
+		if transaction.aborted() {
+			return Err(PublishError::Cancelled);
+		}
+	}
+
+	if !duplicates.is_empty() {
+		return Err(PublishError::DuplicateEntries(duplicates));
+	}
+
 	// TODO some tasks shouldnt be cancelable (i.e. showing the cross button)
 
 	if failed.is_empty() {
@@ -516,7 +986,7 @@ pub fn publish_icon(icon_path: PathBuf, upscale: bool, addon_id: PublishedFileId
 		let preview = match WorkshopIcon::new(icon_path, upscale) {
 			Ok(icon) => icon,
 			Err(error) => {
-				transaction.error(error.to_string(), turbonone!());
+				transaction.error(error.code(), error.data());
 				return;
 			}
 		};
@@ -531,7 +1001,7 @@ pub fn publish_icon(icon_path: PathBuf, upscale: bool, addon_id: PublishedFileId
 				transaction.finished(turbonone!());
 			}
 			Err(error) => {
-				transaction.error(error.to_string(), turbonone!());
+				transaction.error(error.code(), error.data());
 			}
 		};
 	});
@@ -540,150 +1010,281 @@ pub fn publish_icon(icon_path: PathBuf, upscale: bool, addon_id: PublishedFileId
 }
 
 #[tauri::command]
-pub fn publish(
-	content_path_src: PathBuf,
-	icon_path: Option<PathBuf>,
+pub fn create_collection(title: String, items: Vec<PublishedFileId>) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
+
+	rayon::spawn(move || match steam!().create_collection(title, items, &transaction) {
+		Ok((id, legal_agreement)) => {
+			if legal_agreement {
+				crate::path::open("https://steamcommunity.com/workshop/workshoplegalagreement");
+			}
+			transaction.finished(id);
+		}
+		Err(PublishError::Cancelled) => {}
+		Err(error) => transaction.error(error.code(), error.data()),
+	});
+
+	id
+}
+
+#[tauri::command]
+pub fn sync_workshop_metadata(
+	id: PublishedFileId,
 	title: String,
+	description: String,
 	tags: Vec<String>,
 	addon_type: String,
-	upscale: bool,
-	update_id: Option<PublishedFileId>,
-	changes: Option<String>,
+	visibility: PublishedFileVisibility,
 ) -> u32 {
 	let transaction = transaction!();
-	let id = transaction.id;
-
-	let is_updating = update_id.is_some();
-
-	rayon::spawn(move || {
-		let preview = match icon_path {
-			Some(icon_path) => {
-				transaction.status("PUBLISH_PROCESSING_ICON");
+	let transaction_id = transaction.id;
 
-				match WorkshopIcon::new(icon_path, upscale) {
-					Ok(icon) => Some(icon),
-					Err(error) => {
-						transaction.error(error.to_string(), turbonone!());
-						return;
-					}
+	rayon::spawn(
+		move || match steam!().sync_metadata(id, title, description, tags, addon_type, visibility, &transaction) {
+			Ok(legal_agreement) => {
+				if legal_agreement {
+					crate::path::open("https://steamcommunity.com/workshop/workshoplegalagreement");
 				}
+				transaction.finished(turbonone!());
 			}
-			None => {
-				if !is_updating {
-					Some(WorkshopIcon::Default)
-				} else {
-					None
-				}
+			Err(error) => transaction.error(error.code(), error.data()),
+		},
+	);
+
+	transaction_id
+}
+
+/// The description and unknown keys from the content's own addon.json, so they make it into the published GMA.
+/// If it only parsed with comments or trailing commas stripped, the webview is told with `AddonJsonLenient` so it can ask the user to fix it
+fn addon_json_passthrough(content_path_src: &Path, transaction: &Transaction) -> (String, serde_json::Map<String, serde_json::Value>) {
+	let addon_json = content_path_src.join("addon.json");
+	std::fs::read_to_string(&addon_json)
+		.ok()
+		.and_then(|json| crate::from_str_lenient::<GMAMetadata>(&json).ok())
+		.map(|(metadata, lenient)| {
+			if lenient {
+				log::warn!("{} has comments or trailing commas, which aren't valid JSON", addon_json.display());
+				webview_emit!("AddonJsonLenient", (transaction.id, addon_json.clone()));
 			}
-		};
+			metadata
+		})
+		.and_then(|metadata| match metadata {
+			GMAMetadata::Standard { description, extra, .. } => Some((description, extra)),
+			GMAMetadata::Legacy { .. } => None,
+		})
+		.unwrap_or_default()
+}
 
-		transaction.status("PUBLISH_PACKING");
+fn publishing_dir(transaction_id: u32) -> PathBuf {
+	let mut path = app_data!().temp_dir().to_owned();
+	path.pop();
+	path.push("gmpublisher_publishing");
+	path.push(transaction_id.to_string());
+	path
+}
 
-		let mut path = app_data!().temp_dir().to_owned();
-		path.pop();
-		path.push("gmpublisher_publishing");
+/// The GMA that gets packed for upload, in a folder of its own for the transaction so it can be handed to Steam as the content path,
+/// without clashing with any other upload. An existing one is replaced
+fn publishing_gma_path(transaction_id: u32) -> Result<PathBuf, PublishError> {
+	let mut path = publishing_dir(transaction_id);
+	std::fs::create_dir_all(&path)?;
 
-		if std::fs::create_dir_all(&path).is_err() {
-			transaction.error("ERR_IO_ERROR", turbonone!());
-			return;
-		}
+	path.push("gmpublisher.gma");
+	if path.exists() {
+		std::fs::remove_file(&path)?;
+	}
+	Ok(path)
+}
 
-		path.push("gmpublisher.gma");
+/// Cleans up the publishing folder packed or staged for a transaction, once its upload is done
+pub fn remove_publishing_dir(transaction_id: u32) {
+	std::fs::remove_dir_all(publishing_dir(transaction_id)).ok();
+}
 
-		{
-			let gma = GMAFile {
-				// TODO convert to GMAFile::new()
-				path: path.clone(),
-				size: 0,
-				id: None,
-				metadata: Some(GMAMetadata::Standard {
-					title: title.clone(),
-					addon_type: addon_type.clone(),
-					tags: tags.clone(),
-					ignore: app_data!().settings.read().ignore_globs.clone(),
-				}),
-				entries: None,
-				pointers: GMAFilePointers::default(),
-				version: 3,
-				extracted_name: String::new(),
-				modified: None,
-				membuffer: None,
-			};
+impl GMAFile {
+	/// Packs in-memory entries straight into the transaction's publishing content folder, ready to pass to `Steam::update` or `Steam::publish`,
+	/// so generated addons never need to be laid out as loose files first. Remove it with `remove_publishing_dir` once the upload is done
+	pub fn write_to_content_dir(entries: Vec<(String, Vec<u8>)>, metadata: GMAMetadata, transaction_id: u32) -> Result<ContentPath, PublishError> {
+		main_thread_forbidden!();
 
-			if let Err(error) = gma.create(&content_path_src, transaction.clone()) {
-				if !transaction.aborted() {
-					transaction.error(error.to_string(), turbonone!());
-				}
-				return;
-			}
+		if entries.is_empty() {
+			return Err(PublishError::NoEntries);
 		}
 
-		let mut content_path = path.clone();
+		let mut failed: Vec<String> = entries
+			.iter()
+			.map(|(path, _)| path.replace('\\', "/").trim_matches('/').to_lowercase())
+			.filter(|path| !crate::gma::whitelist::check(path))
+			.collect();
+
+		if !failed.is_empty() {
+			failed.sort_unstable();
+			return Err(PublishError::NotWhitelisted(failed));
+		}
+
+		let path = publishing_gma_path(transaction_id)?;
+		GMAFile::new(&path, metadata).create_from_memory(entries, Default::default())?;
+
+		let mut content_path = path;
 		content_path.pop();
 
-		let content_path = match ContentPath::new(content_path) {
-			Ok(content_path) => content_path,
-			Err(error) => {
-				transaction.error(error.to_string(), turbonone!());
-				return;
-			}
-		};
+		ContentPath::new(content_path)
+	}
+}
 
-		transaction.status("PUBLISH_STARTING");
-
-		let (id, result) = if let Some(id) = update_id {
-			(
-				update_id,
-				steam!().update(
-					id,
-					WorkshopUpdateType::Update {
-						title,
-						path: content_path,
-						tags,
-						addon_type,
-						preview,
-						changes,
-					},
-					&transaction,
-				),
-			)
-		} else {
-			steam!().publish(
-				WorkshopUpdateType::Creation {
+/// Packs `content_path_src` into a GMA and uploads it, blocking until Steam is done.
+/// Used by both the `publish` command and the CLI, so it must not depend on the webview.
+/// Returns `PublishError::LegalAgreementRequired` if the upload succeeded but the user still has to accept the Workshop legal agreement.
+pub fn publish_blocking(
+	content_path_src: &Path,
+	preview: Option<WorkshopIcon>,
+	title: String,
+	tags: Vec<String>,
+	addon_type: String,
+	update_id: Option<PublishedFileId>,
+	changes: Option<String>,
+	transaction: &Transaction,
+) -> Result<PublishedFileId, PublishError> {
+	// Don't bother packing if it can't be uploaded
+	if !steam!().is_ready() {
+		return Err(PublishError::SteamNotRunning);
+	}
+
+	transaction.status("PUBLISH_PACKING");
+
+	let packed = (|| -> Result<ContentPath, PublishError> {
+		let mut path = publishing_gma_path(transaction.id)?;
+
+		let (description, extra) = addon_json_passthrough(content_path_src, transaction);
+		let gma = GMAFile::new(
+			&path,
+			GMAMetadata::Standard {
+				title: title.clone(),
+				addon_type: addon_type.clone(),
+				tags: tags.clone(),
+				ignore: app_data!().settings.read().ignore_globs.clone(),
+				description,
+				extra,
+			},
+		);
+
+		gma.create(content_path_src, transaction.clone())?;
+
+		path.pop();
+		ContentPath::new(path)
+	})();
+
+	let content_path = match packed {
+		Ok(content_path) => content_path,
+		Err(error) => {
+			remove_publishing_dir(transaction.id);
+			return Err(error);
+		}
+	};
+
+	transaction.progress_reset();
+	transaction.status("PUBLISH_STARTING");
+
+	let (id, result) = if let Some(id) = update_id {
+		(
+			update_id,
+			steam!().update(
+				id,
+				WorkshopUpdateType::Update {
 					title,
 					path: content_path,
 					tags,
 					addon_type,
-					preview: preview.unwrap(),
+					preview,
+					changes,
 				},
-				&transaction,
-			)
-		};
+				transaction,
+			),
+		)
+	} else {
+		steam!().publish(
+			WorkshopUpdateType::Creation {
+				title,
+				path: content_path,
+				tags,
+				addon_type,
+				preview: preview.unwrap_or(WorkshopIcon::Default),
+			},
+			transaction,
+		)
+	};
+
+	remove_publishing_dir(transaction.id);
+
+	match result {
+		Ok(true) => Err(PublishError::LegalAgreementRequired(id.unwrap())),
+		Ok(false) => Ok(id.unwrap()),
+		Err(error) => {
+			if update_id.is_none() {
+				if let Some(id) = id {
+					steam!().client().ugc().delete_item(id, |_| {});
+				}
+			}
+			Err(error)
+		}
+	}
+}
+
+#[tauri::command]
+pub fn publish(
+	content_path_src: PathBuf,
+	icon_path: Option<PathBuf>,
+	title: String,
+	tags: Vec<String>,
+	addon_type: String,
+	upscale: bool,
+	update_id: Option<PublishedFileId>,
+	changes: Option<String>,
+) -> u32 {
+	let transaction = transaction!();
+	let id = transaction.id;
 
-		ignore! { std::fs::remove_file(path) };
+	rayon::spawn(move || {
+		let preview = match icon_path {
+			Some(icon_path) => {
+				transaction.status("PUBLISH_PROCESSING_ICON");
 
-		match result {
-			Ok(legal_agreement) => {
-				if legal_agreement {
-					crate::path::open("https://steamcommunity.com/workshop/workshoplegalagreement");
+				match WorkshopIcon::new(icon_path, upscale) {
+					Ok(icon) => Some(icon),
+					Err(error) => {
+						transaction.error(error.code(), error.data());
+						return;
+					}
 				}
+			}
+			None => None,
+		};
 
-				let id = id.unwrap();
+		let remember_content_path = |id: PublishedFileId| {
+			app_data!().settings.write().my_workshop_local_paths.insert(id, content_path_src.clone());
+			ignore! { app_data!().settings.read().save() };
+			app_data!().send();
+		};
 
+		match publish_blocking(&content_path_src, preview, title, tags, addon_type, update_id, changes, &transaction) {
+			Ok(id) => {
 				crate::path::open(format!("https://steamcommunity.com/sharedfiles/filedetails/?id={}", id.0));
 
 				transaction.finished(turbonone!());
 
-				app_data!().settings.write().my_workshop_local_paths.insert(id, content_path_src);
-				ignore! { app_data!().settings.read().save() };
-				app_data!().send();
+				remember_content_path(id);
+			}
+			Err(PublishError::LegalAgreementRequired(id)) => {
+				// The UI sends the user to the legal agreement instead
+				let error = PublishError::LegalAgreementRequired(id);
+				transaction.error(error.code(), error.data());
+
+				remember_content_path(id);
 			}
 			Err(error) => {
-				transaction.error(error.to_string(), turbonone!());
-				if !is_updating {
-					if let Some(id) = id {
-						steam!().client().ugc().delete_item(id, |_| {});
-					}
+				if !transaction.aborted() {
+					transaction.error(error.code(), error.data());
 				}
 			}
 		};
@@ -696,8 +1297,10 @@ pub fn publish(
 pub fn verify_icon(path: PathBuf) -> Result<(String, bool), Transaction> {
 	WorkshopIcon::new(&path, false)
 		.and_then(|icon| {
-			let (prefix, can_upscale) = match icon {
-				WorkshopIcon::Custom { format, width, height, .. } => (
+			let (prefix, can_upscale, path) = match icon {
+				WorkshopIcon::Custom {
+					format, width, height, path, ..
+				} => (
 					format!(
 						"data:image/{};base64,",
 						match format {
@@ -708,6 +1311,7 @@ pub fn verify_icon(path: PathBuf) -> Result<(String, bool), Transaction> {
 						}
 					),
 					WorkshopIcon::can_upscale(width, height, format),
+					path,
 				),
 				_ => unreachable!(),
 			};
@@ -720,3 +1324,80 @@ pub fn verify_icon(path: PathBuf) -> Result<(String, bool), Transaction> {
 			transaction
 		})
 }
+
+#[test]
+fn test_content_path_nested_gma() {
+	let dir = std::env::temp_dir().join("gmpublisher_test_content_path_nested_gma");
+	std::fs::remove_dir_all(&dir).ok();
+	std::fs::create_dir_all(dir.join("old/build")).unwrap();
+	std::fs::write(dir.join("addon.gma"), b"").unwrap();
+
+	assert!(ContentPath::new(dir.clone()).is_ok());
+
+	std::fs::write(dir.join("old/build/addon.gma"), b"").unwrap();
+	assert!(matches!(ContentPath::new(dir.clone()), Err(PublishError::NestedGMA(path)) if path == dir.join("old/build/addon.gma")));
+
+	std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_publish_error_serialize() {
+	let error = PublishError::NotWhitelisted(vec!["lua/a.exe".to_string(), "lua/b.exe".to_string()]);
+	assert_eq!(error.to_string(), "ERR_WHITELIST:lua/a.exe\nlua/b.exe");
+	assert_eq!(
+		serde_json::to_value(&error).unwrap(),
+		serde_json::json!({ "code": "ERR_WHITELIST", "data": ["lua/a.exe", "lua/b.exe"] })
+	);
+
+	assert_eq!(
+		serde_json::to_value(PublishError::IconTooLarge(2_000_000)).unwrap(),
+		serde_json::json!({ "code": "ERR_ICON_TOO_LARGE", "data": 2_000_000 })
+	);
+	assert_eq!(
+		serde_json::to_value(PublishError::from(GMAError::UnsupportedVersion(9))).unwrap(),
+		serde_json::json!({ "code": "ERR_GMA_UNSUPPORTED_VERSION", "data": 9 })
+	);
+	assert_eq!(serde_json::to_value(PublishError::NoEntries).unwrap()["data"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_validate_title() {
+	assert!(validate_title("My Addon").is_ok());
+	assert!(validate_title(&"a".repeat(WORKSHOP_TITLE_MAX_LEN)).is_ok());
+
+	assert!(matches!(validate_title(""), Err(PublishError::InvalidTitle(_))));
+	assert!(matches!(validate_title("   "), Err(PublishError::InvalidTitle(_))));
+	assert!(matches!(validate_title("My\nAddon"), Err(PublishError::InvalidTitle(_))));
+	assert!(matches!(
+		validate_title(&"a".repeat(WORKSHOP_TITLE_MAX_LEN + 1)),
+		Err(PublishError::InvalidTitle(_))
+	));
+	// The limit is in bytes, not characters
+	assert!(matches!(
+		validate_title(&"é".repeat(WORKSHOP_TITLE_MAX_LEN / 2 + 1)),
+		Err(PublishError::InvalidTitle(_))
+	));
+}
+
+// Case-insensitive filesystems can't hold both casings in the first place
+#[cfg(target_os = "linux")]
+#[test]
+fn test_scan_content_mixed_case_duplicates() {
+	let dir = std::env::temp_dir().join("gmpublisher_test_scan_content_mixed_case_duplicates");
+	std::fs::remove_dir_all(&dir).ok();
+	std::fs::create_dir_all(dir.join("lua/Folder")).unwrap();
+	std::fs::create_dir_all(dir.join("lua/folder")).unwrap();
+	std::fs::write(dir.join("lua/Folder/File.lua"), b"print('a')").unwrap();
+	std::fs::write(dir.join("lua/folder/file.lua"), b"print('b')").unwrap();
+	std::fs::write(dir.join("lua/folder/other.lua"), b"print('c')").unwrap();
+
+	match scan_content(&dir, false, &crate::transactions::new()) {
+		Err(PublishError::DuplicateEntries(mut paths)) => {
+			paths.sort_unstable();
+			assert_eq!(paths, ["lua/Folder/File.lua", "lua/folder/file.lua"]);
+		}
+		_ => panic!("Expected the mixed-case paths to collide"),
+	}
+
+	std::fs::remove_dir_all(dir).ok();
+}
@@ -1,7 +1,7 @@
 mod websocket;
 
 use lazy_static::lazy_static;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use rayon::ThreadPool;
 use serde::Serialize;
 use std::sync::{
@@ -86,11 +86,27 @@ fn progress_as_int(progress: f64) -> u16 {
 	u16::min((progress * 10000.) as u16, 10000)
 }
 
+/// The outcome of every item a transaction worked through, sent to the frontend just before it finishes or errors
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransactionSummary {
+	pub succeeded: usize,
+	pub failed: usize,
+	pub skipped: usize,
+	/// Every error reported through `failed` or `error`, in the order they happened
+	pub errors: Vec<(String, serde_json::Value)>,
+}
+impl TransactionSummary {
+	fn tallied(&self) -> bool {
+		self.succeeded > 0 || self.failed > 0 || self.skipped > 0
+	}
+}
+
 pub type Transaction = Arc<TransactionInner>;
 #[derive(Debug)]
 pub struct TransactionInner {
 	pub id: u32,
 	aborted: AtomicBool,
+	summary: Mutex<TransactionSummary>,
 }
 impl TransactionInner {
 	fn emit(&self, message: TransactionMessage) {
@@ -149,14 +165,57 @@ impl TransactionInner {
 		}
 	}
 
+	/// Estimated number of seconds until the transaction finishes
+	pub fn eta(&self, secs: u32) {
+		if !self.aborted() {
+			self.emit(TransactionMessage::Eta(self.id, secs));
+		}
+	}
+
+	/// Counts an item as done, for the summary
+	pub fn succeeded(&self) {
+		self.summary.lock().succeeded += 1;
+	}
+
+	/// Counts an item as failed without aborting the transaction, for the summary
+	pub fn failed<S: Into<String>, D: Serialize + Send + 'static>(&self, msg: S, data: D) {
+		let mut summary = self.summary.lock();
+		summary.failed += 1;
+		summary.errors.push((msg.into(), json!(data)));
+	}
+
+	/// Counts an item as deliberately left alone, for the summary
+	pub fn skipped(&self) {
+		self.summary.lock().skipped += 1;
+	}
+
+	pub fn summary(&self) -> TransactionSummary {
+		self.summary.lock().clone()
+	}
+
+	/// Only sent if anything was tallied, so transactions that don't work through items don't get an empty summary
+	fn emit_summary(&self) {
+		let summary = self.summary();
+		if summary.tallied() {
+			self.emit(TransactionMessage::Summary(self.id, json!(summary)));
+		}
+	}
+
 	pub fn error<S: Into<String>, D: Serialize + Send + 'static>(&self, msg: S, data: D) {
 		self.abort();
-		self.emit(TransactionMessage::Error(self.id, msg.into(), json!(data)));
+
+		let msg = msg.into();
+		let data = json!(data);
+		self.summary.lock().errors.push((msg.clone(), data.clone()));
+		self.emit_summary();
+
+		self.emit(TransactionMessage::Error(self.id, msg, data));
 	}
 
 	pub fn finished<D: Serialize + Send + 'static>(&self, data: D) {
 		debug_assert!(!self.aborted(), "Tried to finish an aborted transaction!");
 		self.abort();
+		self.emit_summary();
 		self.emit(TransactionMessage::Finished(self.id, json!(data)));
 	}
 
@@ -196,6 +255,7 @@ pub fn new() -> Transaction {
 	let transaction = Arc::new(TransactionInner {
 		id: TRANSACTIONS.id.fetch_add(1, Ordering::SeqCst),
 		aborted: AtomicBool::new(false),
+		summary: Mutex::new(TransactionSummary::default()),
 	});
 
 	{
@@ -228,3 +288,21 @@ pub fn cancel_transaction(id: u32) {
 pub fn websocket() -> Option<u16> {
 	TRANSACTIONS.websocket.as_ref().map(|socket| socket.port)
 }
+
+#[test]
+fn test_summary() {
+	let transaction = new();
+	assert!(!transaction.summary().tallied());
+
+	transaction.succeeded();
+	transaction.succeeded();
+	transaction.skipped();
+	transaction.failed("ERR_IO_ERROR", "lua/autorun/a.lua");
+
+	let summary = transaction.summary();
+	assert!(summary.tallied());
+	assert_eq!((summary.succeeded, summary.failed, summary.skipped), (2, 1, 1));
+	assert_eq!(summary.errors, vec![("ERR_IO_ERROR".to_string(), json!("lua/autorun/a.lua"))]);
+
+	transaction.cancel();
+}
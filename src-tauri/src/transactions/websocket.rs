@@ -34,6 +34,9 @@ pub enum TransactionMessage {
 	Progress(u32, u16),
 	IncrProgress(u32, u16),
 	ResetProgress(u32),
+	/// Estimated seconds remaining
+	Eta(u32, u32),
+	Summary(u32, serde_json::Value),
 }
 impl TransactionMessage {
 	fn write_json(bytes: &mut Vec<u8>, json: &serde_json::Value) {
@@ -84,6 +87,16 @@ impl TransactionMessage {
 				bytes.write_u8(6).unwrap();
 				bytes.write_u32::<BigEndian>(*id).unwrap();
 			}
+			TransactionMessage::Eta(id, secs) => {
+				bytes.write_u8(7).unwrap();
+				bytes.write_u32::<BigEndian>(*id).unwrap();
+				bytes.write_u32::<BigEndian>(*secs).unwrap();
+			}
+			TransactionMessage::Summary(id, json) => {
+				bytes.write_u8(8).unwrap();
+				bytes.write_u32::<BigEndian>(*id).unwrap();
+				TransactionMessage::write_json(&mut bytes, json);
+			}
 		}
 
 		bytes
@@ -238,6 +251,12 @@ impl TransactionServer {
 			TransactionMessage::ResetProgress(id) => {
 				webview_emit!("TransactionResetProgress", id);
 			}
+			TransactionMessage::Eta(id, secs) => {
+				webview_emit!("TransactionEta", (id, secs));
+			}
+			TransactionMessage::Summary(id, summary) => {
+				webview_emit!("TransactionSummary", (id, summary));
+			}
 		}
 	}
 }
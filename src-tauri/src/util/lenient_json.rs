@@ -0,0 +1,100 @@
+use serde::de::DeserializeOwned;
+
+/// Removes `//` and `/* */` comments and trailing commas, leaving strings untouched
+pub fn strip_json_comments(json: &str) -> String {
+	let mut stripped = String::with_capacity(json.len());
+
+	let mut chars = json.chars().peekable();
+	let mut in_string = false;
+	while let Some(char) = chars.next() {
+		if in_string {
+			stripped.push(char);
+			match char {
+				'\\' => {
+					if let Some(escaped) = chars.next() {
+						stripped.push(escaped);
+					}
+				}
+				'"' => in_string = false,
+				_ => {}
+			}
+			continue;
+		}
+
+		match (char, chars.peek()) {
+			('"', _) => {
+				in_string = true;
+				stripped.push(char);
+			}
+
+			('/', Some('/')) => while chars.next_if(|char| *char != '\n').is_some() {},
+
+			('/', Some('*')) => {
+				chars.next();
+				let mut prev = '\0';
+				for char in chars.by_ref() {
+					if prev == '*' && char == '/' {
+						break;
+					}
+					prev = char;
+				}
+			}
+
+			(']' | '}', _) => {
+				// Drop the comma before the closing bracket, if that's the last thing we kept
+				let trimmed_len = stripped.trim_end().len();
+				if stripped[..trimmed_len].ends_with(',') {
+					stripped.truncate(trimmed_len - 1);
+				}
+				stripped.push(char);
+			}
+
+			_ => stripped.push(char),
+		}
+	}
+
+	stripped
+}
+
+/// Parses JSON strictly, and if that fails, again with comments and trailing commas stripped, as hand-edited addon.json files tend to have.
+/// Also returns whether stripping them was needed, so the user can be told to fix the file
+pub fn from_str_lenient<T: DeserializeOwned>(json: &str) -> Result<(T, bool), serde_json::Error> {
+	match serde_json::from_str(json) {
+		Ok(parsed) => Ok((parsed, false)),
+		Err(error) => match serde_json::from_str(&strip_json_comments(json)) {
+			Ok(parsed) => Ok((parsed, true)),
+			Err(_) => Err(error),
+		},
+	}
+}
+
+#[test]
+fn test_from_str_lenient() {
+	let (value, lenient) = from_str_lenient::<serde_json::Value>(r#"{"title": "a"}"#).unwrap();
+	assert_eq!(value, serde_json::json!({ "title": "a" }));
+	assert!(!lenient);
+
+	let hand_edited = r#"{
+		// The title
+		"title": "a // not a comment, /* nor this */",
+		/* tags,
+		   all of them */
+		"tags": ["fun", "roleplay",],
+		"url": "https://example.com/",
+	}"#;
+	let (value, lenient) = from_str_lenient::<serde_json::Value>(hand_edited).unwrap();
+	assert_eq!(
+		value,
+		serde_json::json!({
+			"title": "a // not a comment, /* nor this */",
+			"tags": ["fun", "roleplay"],
+			"url": "https://example.com/",
+		})
+	);
+	assert!(lenient);
+
+	// Escaped quotes don't end the string
+	let (value, _) = from_str_lenient::<serde_json::Value>(r#"{"title": "a\",}",}"#).unwrap();
+	assert_eq!(value["title"], "a\",}");
+	assert!(from_str_lenient::<serde_json::Value>("not json").is_err());
+}
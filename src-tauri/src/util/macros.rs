@@ -1,3 +1,28 @@
+use std::cell::Cell;
+
+thread_local! {
+	static MAIN_THREAD_ALLOWED: Cell<bool> = Cell::new(false);
+}
+
+/// Runs `f` with `main_thread_forbidden!` turned off on this thread.
+/// Only for work that's known to be small enough not to stall the UI
+pub fn allow_main_thread<R, F: FnOnce() -> R>(f: F) -> R {
+	/// Puts the previous setting back even if `f` panics
+	struct Restore(bool);
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			MAIN_THREAD_ALLOWED.with(|allowed| allowed.set(self.0));
+		}
+	}
+
+	let _restore = Restore(MAIN_THREAD_ALLOWED.with(|allowed| allowed.replace(true)));
+	f()
+}
+
+pub fn main_thread_allowed() -> bool {
+	MAIN_THREAD_ALLOWED.with(|allowed| allowed.get())
+}
+
 #[macro_export]
 macro_rules! ignore {
 	( $x:expr ) => {
@@ -34,7 +59,7 @@ macro_rules! sleep_ms {
 macro_rules! main_thread_forbidden {
 	() => {
 		#[cfg(debug_assertions)]
-		if !*crate::cli::CLI_MODE {
+		if !*crate::cli::CLI_MODE && !crate::main_thread_allowed() {
 			debug_assert_ne!(
 				std::thread::current().name(),
 				Some("main"),
@@ -95,3 +120,12 @@ macro_rules! thread_pool {
 		rayon::ThreadPoolBuilder::new().num_threads(*crate::NUM_THREADS).build().unwrap()
 	};
 }
+
+#[test]
+fn test_allow_main_thread_restores_after_panic() {
+	assert!(std::panic::catch_unwind(|| allow_main_thread::<(), _>(|| panic!("oops"))).is_err());
+	assert!(!main_thread_allowed());
+
+	assert!(allow_main_thread(main_thread_allowed));
+	assert!(!main_thread_allowed());
+}
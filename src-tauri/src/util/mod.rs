@@ -13,5 +13,8 @@ pub use dedup_unsorted::*;
 mod escape_json;
 pub use escape_json::*;
 
+mod lenient_json;
+pub use lenient_json::*;
+
 mod stream;
 pub use stream::*;
@@ -143,6 +143,14 @@ pub fn open<P: AsRef<Path>>(path: P) {
 	}
 }
 
+/// Opens the file in its default application, or if nothing is associated with its type, shows it in the file manager instead
+pub fn open_or_reveal<P: AsRef<Path>>(path: P) {
+	let path = path.as_ref();
+	if opener::open(path).is_err() {
+		open_file_location(path);
+	}
+}
+
 pub fn open_file_location<P: AsRef<Path>>(path: P) {
 	let path = dunce::canonicalize(path.as_ref()).unwrap_or_else(|_| path.as_ref().to_path_buf());
 